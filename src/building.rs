@@ -1,9 +1,15 @@
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 use crate::economy::{BuildingCategory, BuildingDef, BUILDING_DEFS};
+use crate::pathfinding::DIRT_ROAD_SPEED;
 use crate::resources::ResourceMap;
-use crate::road::{sample_catmull_rom, RoadNetwork, SegmentId};
+use crate::road::{arc_fractions, sample_catmull_rom, RoadNetwork, SegmentId};
+use crate::spatial::SpatialGrid;
 use crate::terrain::{Heightmap, TerrainConfig};
+use crate::notifications::Notifications;
+use crate::ui::GameTime;
+use crate::water::{RiverNetwork, WaterBodies};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -15,6 +21,20 @@ const CUBE_HALF: Vec3 = Vec3::new(2.0, 1.5, 2.0); // 4x3x4
 /// Distance from road centerline to lot center (setback + half lot depth).
 const SETBACK: f32 = 3.5;
 
+/// Road travel time (seconds) within which a producer offers work to a home:
+/// 50 units of dirt road.
+const COMMUTE_TIME: f32 = 50.0 / DIRT_ROAD_SPEED;
+
+/// Road travel time (seconds) within which buildings count as neighbors:
+/// 20 units of dirt road.
+const NEIGHBOR_TIME: f32 = 20.0 / DIRT_ROAD_SPEED;
+
+/// Game seconds a building without road access stands before it is abandoned.
+const DISCONNECTED_ABANDON_DELAY: f32 = 30.0;
+
+/// Cell size (world units) of the lot spatial grid.
+const LOT_GRID_CELL_SIZE: f32 = 16.0;
+
 // ---------------------------------------------------------------------------
 // Components
 // ---------------------------------------------------------------------------
@@ -23,6 +43,33 @@ const SETBACK: f32 = 3.5;
 pub struct Building {
     pub def_index: usize,
     pub lot: Entity,
+    pub frontage: Frontage,
+}
+
+/// Where a building meets the road: the segment its lot faces and the
+/// fraction of that segment's length (0..1) at which it sits. Service-radius
+/// checks measure road travel time from here.
+#[derive(Clone, Copy, Debug)]
+pub struct Frontage {
+    pub segment: SegmentId,
+    pub t: f32,
+}
+
+/// Marks a producer with no viable resource left in its extraction radius.
+/// `elapsed` counts game seconds spent exhausted; the producer closes once it
+/// reaches `EXHAUSTED_CLOSE_DELAY`, unless the resource regrows first.
+#[derive(Component, Default)]
+pub struct Exhausted {
+    pub elapsed: f32,
+}
+
+/// Marks a building whose frontage road was removed with no other road in
+/// front of its lot. `elapsed` counts game seconds without road access; the
+/// building is abandoned once it reaches `DISCONNECTED_ABANDON_DELAY`, unless a
+/// road is built in front of it first.
+#[derive(Component, Default)]
+pub struct Disconnected {
+    pub elapsed: f32,
 }
 
 #[derive(Component)]
@@ -52,12 +99,46 @@ impl Default for SpawnTimer {
     }
 }
 
+/// Spatial grid of every lot, kept in sync with `Lot` components by the
+/// `index_added_lot` / `unindex_removed_lot` observers.
+#[derive(Resource)]
+pub struct LotIndex {
+    grid: SpatialGrid<Entity>,
+    /// (center, half_extents, rotation) of each indexed lot.
+    shapes: HashMap<Entity, (Vec2, Vec2, f32)>,
+}
+
+impl Default for LotIndex {
+    fn default() -> Self {
+        Self {
+            grid: SpatialGrid::new(LOT_GRID_CELL_SIZE),
+            shapes: HashMap::new(),
+        }
+    }
+}
+
+impl LotIndex {
+    /// Shapes (center, half_extents, rotation) of lots whose bounding boxes
+    /// overlap the (x, z) box `min..max`.
+    pub fn lots_in_aabb(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (Vec2, Vec2, f32)> + '_ {
+        self.grid
+            .query_aabb(min, max)
+            .into_iter()
+            .filter_map(|entity| self.shapes.get(&entity).copied())
+    }
+
+    /// Lots whose bounding boxes contain an (x, z) point. Callers still run
+    /// `point_in_obb` on them.
+    pub fn lots_at(&self, point: Vec2) -> Vec<Entity> {
+        self.grid.query_radius(point, 0.0)
+    }
+}
 
 // ---------------------------------------------------------------------------
 // OBB overlap detection (2D, XZ plane)
 // ---------------------------------------------------------------------------
 
-fn lot_corners(center: Vec2, half_extents: Vec2, rotation: f32) -> [Vec2; 4] {
+pub fn lot_corners(center: Vec2, half_extents: Vec2, rotation: f32) -> [Vec2; 4] {
     let (sin, cos) = rotation.sin_cos();
     let ax = Vec2::new(cos, sin);
     let ay = Vec2::new(-sin, cos);
@@ -116,38 +197,39 @@ fn obb_overlap(
 
 const ROAD_CLEARANCE: f32 = 1.5;
 
+#[allow(clippy::too_many_arguments)]
 fn validate_placement(
     center: Vec2,
     half_extents: Vec2,
     rotation: f32,
-    lots: &[(Vec2, Vec2, f32)],
+    lot_index: &LotIndex,
     road_network: &RoadNetwork,
     heightmap: &Heightmap,
+    rivers: &RiverNetwork,
+    water_bodies: &WaterBodies,
     config: &TerrainConfig,
 ) -> bool {
+    let corners = lot_corners(center, half_extents, rotation);
+    let min = corners.iter().copied().reduce(Vec2::min).unwrap();
+    let max = corners.iter().copied().reduce(Vec2::max).unwrap();
+
     // 1. Check against existing lots
-    for &(lc, lh, lr) in lots {
+    for (lc, lh, lr) in lot_index.lots_in_aabb(min, max) {
         if obb_overlap(center, half_extents, rotation, lc, lh, lr) {
             return false;
         }
     }
 
     // 2. Check road corridor overlap
-    let corners = lot_corners(center, half_extents, rotation);
-    for segment in road_network.segments().values() {
-        let Some(na) = road_network.node(segment.nodes[0]) else {
-            continue;
-        };
-        let Some(nb) = road_network.node(segment.nodes[1]) else {
+    let margin = Vec2::splat(ROAD_CLEARANCE);
+    for seg_id in road_network.segments_in_aabb(min - margin, max + margin) {
+        let (Some(segment), Some(samples)) = (road_network.segment(seg_id), road_network.segment_curve(seg_id))
+        else {
             continue;
         };
-        let mut path = vec![na.position];
-        path.extend_from_slice(&segment.control_points);
-        path.push(nb.position);
-        let samples = sample_catmull_rom(&path, 4);
         let half_width = segment.width / 2.0 + ROAD_CLEARANCE;
 
-        for sample in &samples {
+        for sample in samples {
             let sp = Vec2::new(sample.x, sample.z);
             for &corner in &corners {
                 if corner.distance(sp) < half_width {
@@ -160,11 +242,11 @@ fn validate_placement(
         }
     }
 
-    // 3. Check water
-    let corners = lot_corners(center, half_extents, rotation);
+    // 3. Check water (oceans, lakes and river channels)
     for &corner in &corners {
-        let h = heightmap.sample_world(corner.x, corner.y, config.map_size);
-        if h < config.water_level {
+        if water_bodies.is_water_world(corner.x, corner.y, config.map_size)
+            || rivers.is_river_world(corner.x, corner.y, config.map_size)
+        {
             return false;
         }
     }
@@ -184,7 +266,7 @@ fn validate_placement(
     true
 }
 
-fn point_in_obb(point: Vec2, center: Vec2, half_extents: Vec2, rotation: f32) -> bool {
+pub fn point_in_obb(point: Vec2, center: Vec2, half_extents: Vec2, rotation: f32) -> bool {
     let d = point - center;
     let (sin, cos) = rotation.sin_cos();
     let local_x = d.x * cos + d.y * sin;
@@ -200,15 +282,17 @@ pub struct Candidate {
     pub position: Vec3,
     pub lot_center: Vec2,
     pub rotation: f32,
-    pub _segment_id: SegmentId,
+    pub frontage: Frontage,
 }
 
 pub fn find_candidates(
     def: &BuildingDef,
     road_network: &RoadNetwork,
     heightmap: &Heightmap,
+    rivers: &RiverNetwork,
+    water_bodies: &WaterBodies,
     config: &TerrainConfig,
-    existing_lots: &[(Vec2, Vec2, f32)],
+    lot_index: &LotIndex,
 ) -> Vec<Candidate> {
     let half_extents = def.lot_half_extents;
     let min_spacing = half_extents.x * 2.0;
@@ -230,6 +314,7 @@ pub fn find_candidates(
         if samples.len() < 2 {
             continue;
         }
+        let fractions = arc_fractions(&samples);
 
         let mut last_left: Option<Vec2> = None;
         let mut last_right: Option<Vec2> = None;
@@ -272,9 +357,11 @@ pub fn find_candidates(
                     lot_center,
                     half_extents,
                     rot,
-                    existing_lots,
+                    lot_index,
                     road_network,
                     heightmap,
+                    rivers,
+                    water_bodies,
                     config,
                 ) {
                     let y = heightmap.sample_world(lot_center.x, lot_center.y, config.map_size);
@@ -282,7 +369,10 @@ pub fn find_candidates(
                         position: Vec3::new(lot_center.x, y, lot_center.y),
                         lot_center,
                         rotation: rot,
-                        _segment_id: seg_id,
+                        frontage: Frontage {
+                            segment: seg_id,
+                            t: fractions[i],
+                        },
                     });
                     *last = Some(lot_center);
                 }
@@ -297,18 +387,32 @@ pub fn find_candidates(
 // Scoring
 // ---------------------------------------------------------------------------
 
+/// Score a candidate lot for a building type. Proximity to other buildings is
+/// measured as road travel time between frontages, so a river without a
+/// bridge keeps neighborhoods apart.
 pub fn score_candidate(
     candidate: &Candidate,
     def: &BuildingDef,
-    buildings: &[(Vec3, usize)],
+    buildings: &[(Frontage, usize)],
+    road_network: &RoadNetwork,
     heightmap: &Heightmap,
     config: &TerrainConfig,
     resource_map: &ResourceMap,
 ) -> f32 {
     let pos = candidate.position;
-    let pos2 = Vec2::new(pos.x, pos.z);
     let mut score: f32 = 0.0;
 
+    let reach = road_network.travel_times_from_point(
+        candidate.frontage.segment,
+        candidate.frontage.t,
+        COMMUTE_TIME.max(NEIGHBOR_TIME),
+    );
+    let within = |frontage: &Frontage, max_time: f32| {
+        reach
+            .time_to_point(road_network, frontage.segment, frontage.t)
+            .is_some_and(|time| time < max_time)
+    };
+
     // Terrain flatness (0-2)
     let flatness = heightmap.sample_flatness(pos.x, pos.z, config.map_size);
     score += flatness * 2.0;
@@ -316,18 +420,19 @@ pub fn score_candidate(
     match def.category {
         BuildingCategory::Producer => {
             // Resource richness (0-8)
-            if let Some(cell) = resource_map.sample_world(pos.x, pos.z, config.map_size)
-                && def.extracts_from == Some(cell.resource)
+            if let Some(entry) = resource_map
+                .known_world(pos.x, pos.z, config.map_size)
+                .find(|entry| def.extracts_from == Some(entry.resource))
             {
-                score += cell.richness * 8.0;
+                score += entry.richness * 8.0;
             }
 
             // Penalty near residential
             let res_nearby = buildings
                 .iter()
-                .filter(|(bp, def_idx)| {
+                .filter(|(frontage, def_idx)| {
                     BUILDING_DEFS[*def_idx].category == BuildingCategory::Residential
-                        && Vec2::new(bp.x, bp.z).distance(pos2) < 20.0
+                        && within(frontage, NEIGHBOR_TIME)
                 })
                 .count();
             score -= res_nearby as f32 * 2.0;
@@ -336,9 +441,9 @@ pub fn score_candidate(
             // Proximity to producers (0-6)
             let producer_count = buildings
                 .iter()
-                .filter(|(bp, def_idx)| {
+                .filter(|(frontage, def_idx)| {
                     BUILDING_DEFS[*def_idx].category == BuildingCategory::Producer
-                        && Vec2::new(bp.x, bp.z).distance(pos2) < 50.0
+                        && within(frontage, COMMUTE_TIME)
                 })
                 .count();
             score += (producer_count as f32 * 2.0).min(6.0);
@@ -346,9 +451,9 @@ pub fn score_candidate(
             // Residential clustering (0-2)
             let res_nearby = buildings
                 .iter()
-                .filter(|(bp, def_idx)| {
+                .filter(|(frontage, def_idx)| {
                     BUILDING_DEFS[*def_idx].category == BuildingCategory::Residential
-                        && Vec2::new(bp.x, bp.z).distance(pos2) < 20.0
+                        && within(frontage, NEIGHBOR_TIME)
                 })
                 .count();
             score += (res_nearby as f32 * 0.5).min(2.0);
@@ -395,6 +500,7 @@ pub fn spawn_building(
             Building {
                 def_index,
                 lot: lot_entity,
+                frontage: candidate.frontage,
             },
         ))
         .id();
@@ -407,6 +513,88 @@ pub fn spawn_building(
     });
 }
 
+/// Sit a building back on the ground at its lot center, after the terrain
+/// under it was edited.
+pub fn drape_building(transform: &mut Transform, lot: &Lot, heightmap: &Heightmap, map_size: f32) {
+    let ground = heightmap.sample_world(lot.center.x, lot.center.y, map_size);
+    transform.translation.y = ground + CUBE_HALF.y;
+}
+
+/// Add a newly inserted lot to the `LotIndex`.
+pub fn index_added_lot(add: On<Add, Lot>, lots: Query<&Lot>, mut index: ResMut<LotIndex>) {
+    let Ok(lot) = lots.get(add.entity) else {
+        return;
+    };
+    let corners = lot_corners(lot.center, lot.half_extents, lot.rotation);
+    let min = corners.iter().copied().reduce(Vec2::min).unwrap();
+    let max = corners.iter().copied().reduce(Vec2::max).unwrap();
+    index.grid.insert(add.entity, min, max);
+    index.shapes.insert(add.entity, (lot.center, lot.half_extents, lot.rotation));
+}
+
+/// Drop a removed or despawned lot from the `LotIndex`.
+pub fn unindex_removed_lot(remove: On<Remove, Lot>, mut index: ResMut<LotIndex>) {
+    index.grid.remove(remove.entity);
+    index.shapes.remove(&remove.entity);
+}
+
+/// Re-attach buildings to the road after their frontage segment was replaced,
+/// e.g. split by a new intersection, by finding the segment point nearest the
+/// front of the lot.
+///
+/// A building with no road left in front of its lot is marked `Disconnected`,
+/// and reconnected if a road is later built there.
+pub fn refresh_frontage(
+    mut commands: Commands,
+    road_network: Res<RoadNetwork>,
+    mut buildings: Query<(Entity, &mut Building, &Lot, Has<Disconnected>)>,
+) {
+    if !road_network.is_changed() {
+        return;
+    }
+
+    for (entity, mut building, lot, disconnected) in &mut buildings {
+        if road_network.segment(building.frontage.segment).is_some() {
+            continue;
+        }
+        let max_distance = SETBACK + lot.half_extents.y + 1.0;
+        match road_network.nearest_segment_point(lot.center, max_distance) {
+            Some((segment, t)) => {
+                building.frontage = Frontage { segment, t };
+                if disconnected {
+                    commands.entity(entity).remove::<Disconnected>();
+                }
+            }
+            None if !disconnected => {
+                commands.entity(entity).insert(Disconnected::default());
+            }
+            None => {}
+        }
+    }
+}
+
+/// Abandon buildings that have been without road access for
+/// `DISCONNECTED_ABANDON_DELAY` game seconds, despawning the building and its
+/// lot.
+pub fn abandon_disconnected(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_time: Res<GameTime>,
+    mut buildings: Query<(Entity, &Building, &mut Disconnected)>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let dt = time.delta_secs() * game_time.speed.multiplier();
+    for (entity, building, mut disconnected) in &mut buildings {
+        disconnected.elapsed += dt;
+        if disconnected.elapsed >= DISCONNECTED_ABANDON_DELAY {
+            commands.entity(entity).despawn();
+            commands.entity(building.lot).despawn();
+            let def = &BUILDING_DEFS[building.def_index];
+            notifications.push(format!("{} abandoned: no road access", def.label), 4.0);
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Debug visualization
 // ---------------------------------------------------------------------------
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::building::{lot_corners, point_in_obb, Building, Lot, LotIndex};
+use crate::economy::BUILDING_DEFS;
+use crate::road::{ActiveTool, RoadNetwork, RoadPlacementState, SegmentId, SEGMENT_PICK_RADIUS};
+use crate::terrain::{Heightmap, TerrainConfig};
+use crate::notifications::Notifications;
+use crate::ui::CursorWorldPosition;
+
+// ---------------------------------------------------------------------------
+// Targets
+// ---------------------------------------------------------------------------
+
+/// What the bulldozer would remove at the cursor.
+#[derive(Clone, Copy)]
+enum BulldozeTarget {
+    /// A building and the lot it stands on.
+    Building { building: Entity, lot: Entity },
+    Segment(SegmentId),
+}
+
+/// Building whose lot contains the cursor, else the road segment nearest to
+/// it within `SEGMENT_PICK_RADIUS`. Only lots `LotIndex` files under the
+/// cursor are tested.
+fn hovered_target(
+    position: Vec3,
+    road_network: &RoadNetwork,
+    lot_index: &LotIndex,
+    lots: &Query<&Lot>,
+) -> Option<BulldozeTarget> {
+    let point = Vec2::new(position.x, position.z);
+    for lot_entity in lot_index.lots_at(point) {
+        let Ok(lot) = lots.get(lot_entity) else { continue };
+        if point_in_obb(point, lot.center, lot.half_extents, lot.rotation) {
+            return Some(BulldozeTarget::Building {
+                building: lot.building,
+                lot: lot_entity,
+            });
+        }
+    }
+    road_network
+        .nearest_segment_point(point, SEGMENT_PICK_RADIUS)
+        .map(|(segment, _)| BulldozeTarget::Segment(segment))
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Toggle the bulldoze tool with B key.
+pub fn toggle_bulldoze_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut active_tool: ResMut<ActiveTool>,
+    mut placement: ResMut<RoadPlacementState>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyB) {
+        if *active_tool == ActiveTool::Bulldoze {
+            *active_tool = ActiveTool::None;
+        } else {
+            *active_tool = ActiveTool::Bulldoze;
+        }
+        placement.points.clear();
+    }
+}
+
+/// Remove the hovered building or road segment on left click.
+///
+/// Removing a building despawns it with its lot. Removing a road also drops
+/// any nodes left without segments; buildings that faced it are re-attached
+/// or marked disconnected by `refresh_frontage`.
+#[allow(clippy::too_many_arguments)]
+pub fn bulldoze_input(
+    mut commands: Commands,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    egui_input: Res<EguiWantsInput>,
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    mut road_network: ResMut<RoadNetwork>,
+    lot_index: Res<LotIndex>,
+    lots: Query<&Lot>,
+    buildings: Query<&Building>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if *active_tool != ActiveTool::Bulldoze
+        || !mouse_buttons.just_pressed(MouseButton::Left)
+        || egui_input.wants_any_pointer_input()
+    {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+
+    match hovered_target(pos, &road_network, &lot_index, &lots) {
+        Some(BulldozeTarget::Building { building, lot }) => {
+            if let Ok(b) = buildings.get(building) {
+                notifications.push(format!("{} demolished", BUILDING_DEFS[b.def_index].label), 3.0);
+            }
+            commands.entity(building).despawn();
+            commands.entity(lot).despawn();
+        }
+        Some(BulldozeTarget::Segment(segment)) => {
+            road_network.remove_segment(segment);
+            road_network.remove_orphan_nodes();
+            notifications.push("Road removed", 3.0);
+        }
+        None => {}
+    }
+}
+
+/// Highlight what a click would remove: the lot outline of a hovered
+/// building, or the full length of a hovered road segment.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_bulldoze_highlight(
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    road_network: Res<RoadNetwork>,
+    lot_index: Res<LotIndex>,
+    lots: Query<&Lot>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
+    mut gizmos: Gizmos,
+) {
+    if *active_tool != ActiveTool::Bulldoze {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+
+    let color = Color::srgb(1.0, 0.2, 0.15);
+    match hovered_target(pos, &road_network, &lot_index, &lots) {
+        Some(BulldozeTarget::Building { lot, .. }) => {
+            let Ok(lot) = lots.get(lot) else {
+                return;
+            };
+            let corners = lot_corners(lot.center, lot.half_extents, lot.rotation);
+            for i in 0..4 {
+                let a = corners[i];
+                let b = corners[(i + 1) % 4];
+                let ya = heightmap.sample_world(a.x, a.y, config.map_size) + 0.3;
+                let yb = heightmap.sample_world(b.x, b.y, config.map_size) + 0.3;
+                gizmos.line(Vec3::new(a.x, ya, a.y), Vec3::new(b.x, yb, b.y), color);
+            }
+        }
+        Some(BulldozeTarget::Segment(segment)) => {
+            let Some(curve) = road_network.segment_curve(segment) else {
+                return;
+            };
+            let lift = Vec3::Y * 0.4;
+            for pair in curve.windows(2) {
+                gizmos.line(pair[0] + lift, pair[1] + lift, color);
+            }
+        }
+        None => {}
+    }
+}
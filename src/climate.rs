@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use crate::terrain::TerrainConfig;
+use crate::water::{RiverNetwork, WaterBodies};
+
+/// Distance (world units) over which moisture from open water fades to ~37%.
+const MOISTURE_FALLOFF: f32 = 40.0;
+
+/// Sea-level temperature at the north (row 0) and south edges of the map.
+const NORTH_TEMPERATURE: f32 = 0.3;
+const SOUTH_TEMPERATURE: f32 = 0.8;
+
+/// Temperature drop from sea level to the top of `height_scale`.
+const LAPSE_RATE: f32 = 0.5;
+
+/// Per-cell moisture and temperature, same resolution as the heightmap.
+/// Both are normalized to 0.0–1.0.
+///
+/// - `moisture`: wettest next to lakes, the sea and rivers, falling off with
+///   distance, broken up by noise so dry and wet patches appear inland.
+/// - `temperature`: sea-level temperature from a north–south (latitude-like)
+///   gradient plus noise. Use [`ClimateMap::temperature_at`] for the
+///   temperature at a given elevation.
+#[derive(Resource)]
+pub struct ClimateMap {
+    pub moisture: Vec<f32>,
+    pub temperature: Vec<f32>,
+    pub resolution: u32,
+}
+
+impl ClimateMap {
+    pub fn moisture(&self, row: u32, col: u32) -> f32 {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.moisture[(row * self.resolution + col) as usize]
+    }
+
+    /// Temperature at a cell, cooled by `elevation_t` (height / `height_scale`).
+    pub fn temperature_at(&self, row: u32, col: u32, elevation_t: f32) -> f32 {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        let base = self.temperature[(row * self.resolution + col) as usize];
+        (base - elevation_t * LAPSE_RATE).clamp(0.0, 1.0)
+    }
+}
+
+/// Distance in cells from every cell to the nearest cell where `is_water`
+/// holds, using a two-pass chamfer transform (1 for edges, √2 for diagonals).
+fn water_distance(is_water: impl Fn(u32, u32) -> bool, res: u32) -> Vec<f32> {
+    let n = res as usize;
+    let mut dist = vec![f32::INFINITY; n * n];
+    for row in 0..res {
+        for col in 0..res {
+            if is_water(row, col) {
+                dist[(row * res + col) as usize] = 0.0;
+            }
+        }
+    }
+
+    let diagonal = std::f32::consts::SQRT_2;
+    let forward: [(i32, i32, f32); 4] = [(-1, -1, diagonal), (-1, 0, 1.0), (-1, 1, diagonal), (0, -1, 1.0)];
+    let backward: [(i32, i32, f32); 4] = [(1, 1, diagonal), (1, 0, 1.0), (1, -1, diagonal), (0, 1, 1.0)];
+
+    let mut relax = |row: usize, col: usize, offsets: &[(i32, i32, f32); 4]| {
+        let i = row * n + col;
+        for &(dr, dc, cost) in offsets {
+            let r = row as i32 + dr;
+            let c = col as i32 + dc;
+            if r < 0 || c < 0 || r >= n as i32 || c >= n as i32 {
+                continue;
+            }
+            let candidate = dist[r as usize * n + c as usize] + cost;
+            if candidate < dist[i] {
+                dist[i] = candidate;
+            }
+        }
+    };
+
+    for row in 0..n {
+        for col in 0..n {
+            relax(row, col, &forward);
+        }
+    }
+    for row in (0..n).rev() {
+        for col in (0..n).rev() {
+            relax(row, col, &backward);
+        }
+    }
+
+    dist
+}
+
+/// Build the moisture and temperature fields.
+///
+/// Runs after rivers and water bodies are known and before
+/// `generate_biome_map`, which uses both fields to pick vegetation biomes.
+pub fn generate_climate_map(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    rivers: Res<RiverNetwork>,
+    water_bodies: Res<WaterBodies>,
+) {
+    let res = config.resolution;
+    let cell_size = config.map_size / res as f32;
+
+    let moisture_noise = Fbm::<Perlin>::new(config.derive_seed(700))
+        .set_octaves(4)
+        .set_frequency(4.0 / config.map_size as f64)
+        .set_persistence(0.5);
+
+    let temperature_noise = Fbm::<Perlin>::new(config.derive_seed(800))
+        .set_octaves(2)
+        .set_frequency(2.0 / config.map_size as f64)
+        .set_persistence(0.5);
+
+    let distance = water_distance(|row, col| water_bodies.is_water(row, col) || rivers.is_river(row, col), res);
+    let mut moisture = Vec::with_capacity(distance.len());
+    let mut temperature = Vec::with_capacity(distance.len());
+
+    for row in 0..res {
+        for col in 0..res {
+            let x = col as f64 * cell_size as f64;
+            let z = row as f64 * cell_size as f64;
+
+            // Proximity to water dominates; noise adds inland wet and dry patches
+            let proximity = (-distance[(row * res + col) as usize] * cell_size / MOISTURE_FALLOFF).exp();
+            let wetness = (moisture_noise.get([x, z]) as f32 * 0.5 + 0.5).clamp(0.0, 1.0);
+            moisture.push((proximity * 0.7 + wetness * 0.5 - 0.15).clamp(0.0, 1.0));
+
+            let latitude = row as f32 / (res - 1) as f32;
+            let base = NORTH_TEMPERATURE + (SOUTH_TEMPERATURE - NORTH_TEMPERATURE) * latitude;
+            let variation = temperature_noise.get([x, z]) as f32 * 0.1;
+            temperature.push((base + variation).clamp(0.0, 1.0));
+        }
+    }
+
+    commands.insert_resource(ClimateMap {
+        moisture,
+        temperature,
+        resolution: res,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::SQRT_2;
+
+    #[test]
+    fn water_distance_counts_edge_and_diagonal_steps() {
+        let res = 7;
+        let distance = water_distance(|row, col| (row, col) == (3, 3), res);
+        let at = |row: u32, col: u32| distance[(row * res + col) as usize];
+
+        assert_eq!(at(3, 3), 0.0);
+        assert_eq!(at(3, 4), 1.0);
+        assert_eq!(at(2, 3), 1.0);
+        assert!((at(4, 4) - SQRT_2).abs() < 1e-6);
+        assert!((at(2, 2) - SQRT_2).abs() < 1e-6);
+        assert_eq!(at(3, 6), 3.0);
+        assert!((at(1, 4) - (1.0 + SQRT_2)).abs() < 1e-6);
+        assert!((at(0, 0) - 3.0 * SQRT_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn water_distance_takes_nearest_of_several() {
+        // River along column 0 and a pond in the far corner
+        let res = 9;
+        let distance = water_distance(|row, col| col == 0 || (row, col) == (8, 8), res);
+        let at = |row: u32, col: u32| distance[(row * res + col) as usize];
+
+        assert!((0..res).all(|row| at(row, 0) == 0.0));
+        assert_eq!(at(4, 3), 3.0);
+        assert_eq!(at(8, 6), 2.0);
+        assert!((at(7, 7) - SQRT_2).abs() < 1e-6);
+    }
+}
@@ -1,10 +1,17 @@
 use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
 
-use crate::building::{find_candidates, score_candidate, spawn_building, Building, Lot, SpawnTimer};
-use crate::resources::{ResourceMap, ResourceType};
-use crate::road::{sample_catmull_rom, RoadNetwork};
+use crate::building::{
+    find_candidates, score_candidate, spawn_building, Building, Disconnected, Exhausted, Frontage, LotIndex,
+    SpawnTimer,
+};
+use crate::pathfinding::{TravelTimes, DIRT_ROAD_SPEED};
+use crate::resources::{DepositId, ResourceDeposits, ResourceMap, ResourceType, VIABLE_RICHNESS};
+use crate::road::{arc_fractions, RoadNetwork, SegmentId};
 use crate::terrain::{Heightmap, TerrainConfig};
-use crate::ui::{GameTime, Notifications};
+use crate::notifications::Notifications;
+use crate::ui::GameTime;
+use crate::water::{RiverNetwork, WaterBodies};
 
 // ---------------------------------------------------------------------------
 // Building definitions
@@ -23,7 +30,12 @@ pub struct BuildingDef {
     pub workers_required: u32,
     pub workers_provided: u32,
     pub extracts_from: Option<ResourceType>,
+    /// Road travel distance (as driven on dirt) a producer works out to, and
+    /// keeps between itself and other extractors of its resource.
     pub extraction_radius: f32,
+    /// Richness removed per game second from every matching cell the
+    /// producer reaches.
+    pub extraction_rate: f32,
 }
 
 pub static BUILDING_DEFS: &[BuildingDef] = &[
@@ -36,6 +48,7 @@ pub static BUILDING_DEFS: &[BuildingDef] = &[
         workers_provided: 0,
         extracts_from: Some(ResourceType::Timber),
         extraction_radius: 60.0,
+        extraction_rate: 0.005,
     },
     // 1: Residential
     BuildingDef {
@@ -46,9 +59,46 @@ pub static BUILDING_DEFS: &[BuildingDef] = &[
         workers_provided: 2,
         extracts_from: None,
         extraction_radius: 0.0,
+        extraction_rate: 0.0,
     },
 ];
 
+/// Smallest deposit (summed richness at generation) worth opening a
+/// producer on, so isolated specks of resource are ignored.
+const MIN_DEPOSIT_RESERVES: f32 = 10.0;
+
+/// Deposits with less than this fraction of their reserves left are
+/// considered worked out.
+const MIN_DEPOSIT_REMAINING: f32 = 0.1;
+
+/// Game seconds a producer stays open on exhausted ground before closing.
+const EXHAUSTED_CLOSE_DELAY: f32 = 30.0;
+
+/// How far (world units) from a reachable road point a producer works the
+/// ground.
+const WORK_DISTANCE: f32 = 15.0;
+
+// ---------------------------------------------------------------------------
+// Resources
+// ---------------------------------------------------------------------------
+
+/// Accumulates game time between extraction/regrowth ticks, so the resource
+/// map is only modified (and flagged changed) once per `interval`.
+#[derive(Resource)]
+pub struct ExtractionTimer {
+    pub accumulator: f32,
+    pub interval: f32,
+}
+
+impl Default for ExtractionTimer {
+    fn default() -> Self {
+        Self {
+            accumulator: 0.0,
+            interval: 1.0,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Debug info resource
 // ---------------------------------------------------------------------------
@@ -65,6 +115,10 @@ pub struct EconomyDebug {
     pub residential_candidates: usize,
     pub last_spawn: Option<String>,
     pub best_score: Option<f32>,
+    /// Producers with no viable resource left in their extraction radius.
+    pub exhausted_producers: u32,
+    /// Producers closed for exhaustion since the game started.
+    pub closed_producers: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -78,13 +132,16 @@ pub fn evaluate_and_spawn(
     game_time: Res<GameTime>,
     road_network: Res<RoadNetwork>,
     heightmap: Res<Heightmap>,
+    rivers: Res<RiverNetwork>,
+    water_bodies: Res<WaterBodies>,
     config: Res<TerrainConfig>,
-    resource_map: Res<ResourceMap>,
+    // Grouped to stay within Bevy's 16-parameter system limit
+    (resource_map, deposits): (Res<ResourceMap>, Res<ResourceDeposits>),
     mut spawn_timer: ResMut<SpawnTimer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    buildings_query: Query<(&Building, &Transform)>,
-    lots_query: Query<&Lot>,
+    buildings_query: Query<&Building>,
+    lot_index: Res<LotIndex>,
     mut notifications: ResMut<Notifications>,
     mut debug: ResMut<EconomyDebug>,
 ) {
@@ -109,14 +166,9 @@ pub fn evaluate_and_spawn(
         return;
     }
 
-    let existing_lots: Vec<(Vec2, Vec2, f32)> = lots_query
-        .iter()
-        .map(|lot| (lot.center, lot.half_extents, lot.rotation))
-        .collect();
-
-    let existing_buildings: Vec<(Vec3, usize)> = buildings_query
+    let existing_buildings: Vec<(Frontage, usize)> = buildings_query
         .iter()
-        .map(|(b, t)| (t.translation, b.def_index))
+        .map(|b| (b.frontage, b.def_index))
         .collect();
 
     // Compute global worker counts
@@ -142,6 +194,7 @@ pub fn evaluate_and_spawn(
                     def,
                     &road_network,
                     &resource_map,
+                    &deposits,
                     &config,
                     &existing_buildings,
                 );
@@ -167,8 +220,10 @@ pub fn evaluate_and_spawn(
             def,
             &road_network,
             &heightmap,
+            &rivers,
+            &water_bodies,
             &config,
-            &existing_lots,
+            &lot_index,
         );
 
         match def.category {
@@ -187,6 +242,7 @@ pub fn evaluate_and_spawn(
                     c,
                     def,
                     &existing_buildings,
+                    &road_network,
                     &heightmap,
                     &config,
                     &resource_map,
@@ -224,53 +280,321 @@ pub fn evaluate_and_spawn(
 // Viability checks
 // ---------------------------------------------------------------------------
 
+/// A producer opens on a road that passes over a known (surface or surveyed)
+/// deposit of its resource,
+/// where the deposit is large enough, not worked out, the ground under the
+/// road is still rich enough, and no extractor of the same kind is within
+/// `extraction_radius` of road travel (as driven on dirt).
 fn is_producer_viable(
     def: &BuildingDef,
     road_network: &RoadNetwork,
     resource_map: &ResourceMap,
+    deposits: &ResourceDeposits,
     config: &TerrainConfig,
-    existing_buildings: &[(Vec3, usize)],
+    existing_buildings: &[(Frontage, usize)],
 ) -> (bool, &'static str) {
     let extract_resource = match def.extracts_from {
         Some(r) => r,
         None => return (false, "no extraction resource"),
     };
 
-    // Walk sampled points along every road segment, not just nodes,
+    // Road reach of every existing extractor of the same resource
+    let overlap_time = def.extraction_radius / DIRT_ROAD_SPEED;
+    let extractor_reach: Vec<TravelTimes> = existing_buildings
+        .iter()
+        .filter(|(_, b_def_idx)| BUILDING_DEFS[*b_def_idx].extracts_from == def.extracts_from)
+        .map(|(frontage, _)| road_network.travel_times_from_point(frontage.segment, frontage.t, overlap_time))
+        .collect();
+
+    // Remaining fraction per deposit, computed once per check
+    let mut remaining: HashMap<DepositId, f32> = HashMap::new();
+    let mut failure = Unviable::NoResource;
+
+    // Only segments passing over a deposit of the resource can qualify
+    let mut candidates: HashSet<SegmentId> = HashSet::new();
+    for deposit in deposits.deposits.iter().filter(|d| d.resource == extract_resource) {
+        let res = resource_map.resolution;
+        let (min, max) = deposit.bounds.expand(1, res).world_bounds(res, config.map_size);
+        candidates.extend(road_network.segments_in_aabb(min, max));
+    }
+
+    // Walk sampled points along each segment, not just nodes,
     // so roads that pass through a resource zone are detected even when
     // the endpoint nodes sit outside the zone.
-    let mut found_resource_on_road = false;
-    for segment in road_network.segments().values() {
-        let Some(na) = road_network.node(segment.nodes[0]) else {
+    for segment_id in candidates {
+        let Some(curve) = road_network.segment_curve(segment_id) else {
             continue;
         };
-        let Some(nb) = road_network.node(segment.nodes[1]) else {
+        let fractions = arc_fractions(curve);
+
+        for (&pos, t) in curve.iter().zip(fractions) {
+            // Unsurveyed subsurface resources are invisible to the economy
+            let Some(entry) = resource_map
+                .known_world(pos.x, pos.z, config.map_size)
+                .find(|entry| entry.resource == extract_resource)
+            else {
+                continue;
+            };
+            let Some(deposit) =
+                deposits.deposit_at_world(pos.x, pos.z, config.map_size, extract_resource)
+            else {
+                continue;
+            };
+
+            let here = if deposit.total_reserves < MIN_DEPOSIT_RESERVES {
+                Unviable::DepositTooSmall
+            } else if *remaining
+                .entry(deposit.id)
+                .or_insert_with(|| deposit.remaining_fraction(resource_map))
+                < MIN_DEPOSIT_REMAINING
+                || entry.richness <= VIABLE_RICHNESS
+            {
+                Unviable::Depleted
+            } else if extractor_reach
+                .iter()
+                .any(|reach| reach.time_to_point(road_network, segment_id, t).is_some())
+            {
+                Unviable::ExtractorNearby
+            } else {
+                return (true, "ok");
+            };
+            failure = failure.max(here);
+        }
+    }
+
+    let reason = match failure {
+        Unviable::NoResource => "no resource on road",
+        Unviable::DepositTooSmall => "deposit too small",
+        Unviable::Depleted => "deposit depleted",
+        Unviable::ExtractorNearby => "extractor nearby",
+    };
+    (false, reason)
+}
+
+/// Why a road point can't host a new producer. When no point qualifies, the
+/// greatest cause found is reported, so "extractor nearby" wins over a
+/// depleted deposit, which wins over a small one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Unviable {
+    NoResource,
+    DepositTooSmall,
+    Depleted,
+    ExtractorNearby,
+}
+
+/// Cells a producer works: those within `WORK_DISTANCE` of any road point it
+/// can reach from its frontage within `extraction_radius` of road travel (as
+/// driven on dirt), the same reach that spaces extractors apart. Ground across
+/// a river with no bridge is out of reach, however close.
+fn reachable_cells(
+    def: &BuildingDef,
+    frontage: Frontage,
+    road_network: &RoadNetwork,
+    resource_map: &ResourceMap,
+    map_size: f32,
+) -> HashSet<(u32, u32)> {
+    let reach = road_network.travel_times_from_point(
+        frontage.segment,
+        frontage.t,
+        def.extraction_radius / DIRT_ROAD_SPEED,
+    );
+
+    // The frontage segment plus every segment leaving a reached node
+    let mut segments: HashSet<SegmentId> = HashSet::from([frontage.segment]);
+    for (node, _) in reach.iter() {
+        if let Some(node) = road_network.node(node) {
+            segments.extend(node.segments.iter().copied());
+        }
+    }
+
+    let mut cells = HashSet::new();
+    for segment in segments {
+        let Some(curve) = road_network.segment_curve(segment) else {
             continue;
         };
-        let mut path = vec![na.position];
-        path.extend_from_slice(&segment.control_points);
-        path.push(nb.position);
-
-        for pos in sample_catmull_rom(&path, 4) {
-            if let Some(cell) = resource_map.sample_world(pos.x, pos.z, config.map_size)
-                && cell.resource == extract_resource
-                && cell.richness > 0.2
+        for (&pos, t) in curve.iter().zip(arc_fractions(curve)) {
+            if reach.time_to_point(road_network, segment, t).is_some() {
+                cells.extend(resource_map.cells_in_radius(Vec2::new(pos.x, pos.z), WORK_DISTANCE, map_size));
+            }
+        }
+    }
+    cells
+}
+
+// ---------------------------------------------------------------------------
+// Extraction and regrowth
+// ---------------------------------------------------------------------------
+
+/// Deplete resources under producers, regrow renewable ones, and close
+/// producers that have run out.
+///
+/// Each producer works the cells it can reach by road (`reachable_cells`),
+/// and surveys them too, revealing any hidden subsurface resources there.
+///
+/// Every tick, renewable cells below capacity regain
+/// `ResourceType::regrowth_rate` up to their capacity, then each producer
+/// removes its `extraction_rate` from every matching cell it reaches. A
+/// producer with no cell above
+/// `VIABLE_RICHNESS` left in reach is marked `Exhausted`, and closes (building
+/// and lot despawned) after `EXHAUSTED_CLOSE_DELAY` game seconds unless the
+/// resource regrows first.
+///
+/// Producers cut off from the road are left alone (and their closing timer
+/// paused) until they reconnect: `abandon_disconnected` owns their removal.
+#[allow(clippy::too_many_arguments)]
+pub fn extract_resources(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_time: Res<GameTime>,
+    config: Res<TerrainConfig>,
+    mut timer: ResMut<ExtractionTimer>,
+    road_network: Res<RoadNetwork>,
+    mut resource_map: ResMut<ResourceMap>,
+    mut producers: Query<(Entity, &Building, Option<&mut Exhausted>), Without<Disconnected>>,
+    mut notifications: ResMut<Notifications>,
+    mut debug: ResMut<EconomyDebug>,
+) {
+    timer.accumulator += time.delta_secs() * game_time.speed.multiplier();
+    if timer.accumulator < timer.interval {
+        return;
+    }
+    timer.accumulator -= timer.interval;
+    let dt = timer.interval;
+
+    // Only borrow the map mutably when something changes, so the resource
+    // overlay isn't repainted for nothing
+    if resource_map.is_regrowing() {
+        resource_map.regrow(dt);
+    }
+
+    let mut exhausted_count = 0;
+    for (entity, building, exhausted) in &mut producers {
+        let def = &BUILDING_DEFS[building.def_index];
+        let Some(resource) = def.extracts_from else {
+            continue;
+        };
+
+        let cells = reachable_cells(def, building.frontage, &road_network, &resource_map, config.map_size);
+
+        // Working the ground reveals what lies under it
+        if cells.iter().any(|&(row, col)| !resource_map.is_surveyed(row, col)) {
+            resource_map.survey_cells(cells.iter().copied());
+        }
+
+        let mut viable = false;
+        for (row, col) in cells {
+            // Other resources in the cell are left for their own producers.
+            // Worked-out cells are skipped rather than rewritten at zero.
+            let Some(entry) = resource_map.get(row, col).get(resource) else {
+                continue;
+            };
+            if entry.richness > 0.0
+                && let Some(left) = resource_map.extract(row, col, resource, def.extraction_rate * dt)
             {
-                found_resource_on_road = true;
-                let has_nearby = existing_buildings.iter().any(|(b_pos, b_def_idx)| {
-                    let b_def = &BUILDING_DEFS[*b_def_idx];
-                    b_def.extracts_from == def.extracts_from
-                        && b_pos.distance(pos) < def.extraction_radius
-                });
-                if !has_nearby {
-                    return (true, "ok");
+                viable |= left > VIABLE_RICHNESS;
+            }
+        }
+
+        match (viable, exhausted) {
+            (true, Some(_)) => {
+                commands.entity(entity).remove::<Exhausted>();
+            }
+            (true, None) => {}
+            (false, Some(mut exhausted)) => {
+                exhausted.elapsed += dt;
+                if exhausted.elapsed >= EXHAUSTED_CLOSE_DELAY {
+                    commands.entity(entity).despawn();
+                    commands.entity(building.lot).despawn();
+                    debug.closed_producers += 1;
+                    notifications.push(
+                        format!("{} closed: {} exhausted", def.label, resource.label()),
+                        4.0,
+                    );
+                } else {
+                    exhausted_count += 1;
                 }
             }
+            (false, None) => {
+                commands.entity(entity).insert(Exhausted::default());
+                exhausted_count += 1;
+                notifications.push(format!("{} has run out of {}", def.label, resource.label()), 3.0);
+            }
         }
     }
-    if found_resource_on_road {
-        (false, "extractor nearby")
-    } else {
-        (false, "no resource on road")
+    debug.exhausted_producers = exhausted_count;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{ResourceCell, ResourceEntry};
+    use crate::road::RoadType;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// 16x16 cells of 4 units over a 64-unit map, all holding the same
+    /// richness of one resource.
+    fn uniform_map(resource: ResourceType, richness: f32) -> ResourceMap {
+        let cell = ResourceCell {
+            entries: vec![ResourceEntry {
+                resource,
+                richness,
+                capacity: richness,
+            }],
+        };
+        ResourceMap::new(vec![cell; 16 * 16], 16)
+    }
+
+    fn straight_road(network: &mut RoadNetwork, from: Vec3, to: Vec3) -> SegmentId {
+        let a = network.add_node(from);
+        let b = network.add_node(to);
+        network.add_segment(a, b, Vec::new(), RoadType::Dirt, 2.0)
+    }
+
+    #[test]
+    fn extraction_follows_road_reach() {
+        // The camp's road, and a parallel road 24 units away that never joins
+        // it, as if across a river
+        let mut network = RoadNetwork::default();
+        let road = straight_road(&mut network, Vec3::new(-20.0, 0.0, 0.0), Vec3::new(20.0, 0.0, 0.0));
+        straight_road(&mut network, Vec3::new(-20.0, 0.0, 24.0), Vec3::new(20.0, 0.0, 24.0));
+
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(GameTime::default());
+        world.insert_resource(TerrainConfig {
+            map_size: 64.0,
+            ..default()
+        });
+        // Due a tick whatever the frame time
+        world.insert_resource(ExtractionTimer {
+            accumulator: 1.0,
+            ..default()
+        });
+        world.insert_resource(network);
+        world.insert_resource(uniform_map(ResourceType::Timber, 0.003));
+        world.init_resource::<Notifications>();
+        world.init_resource::<EconomyDebug>();
+        let lot = world.spawn_empty().id();
+        let camp = world
+            .spawn(Building {
+                def_index: 0,
+                lot,
+                frontage: Frontage { segment: road, t: 0.5 },
+            })
+            .id();
+
+        world.run_system_once(extract_resources).unwrap();
+
+        // Ground along the camp's road is worked down to zero, so it's exhausted
+        let map = world.resource::<ResourceMap>();
+        let timber = |row: u32, col: u32| map.get(row, col).get(ResourceType::Timber).unwrap().richness;
+        assert_eq!(timber(8, 8), 0.0);
+        assert!(world.get::<Exhausted>(camp).is_some());
+        assert_eq!(world.resource::<EconomyDebug>().exhausted_producers, 1);
+        // Along the unconnected road (z = 26), well inside the old straight-line
+        // radius, nothing is taken
+        assert_eq!(timber(14, 8), 0.003);
+        // Nor far from any road
+        assert_eq!(timber(0, 0), 0.003);
     }
 }
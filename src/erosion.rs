@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+
+use crate::heightmap_io::HeightmapImport;
+use crate::terrain::{Heightmap, TerrainConfig};
+
+// ---------------------------------------------------------------------------
+// Hydraulic erosion parameters
+// ---------------------------------------------------------------------------
+//
+// Droplets run on heights normalized to 0.0–1.0, so these values are
+// independent of `height_scale`.
+
+/// Maximum steps a droplet takes before it is discarded.
+const DROPLET_LIFETIME: u32 = 30;
+/// How much a droplet keeps its previous direction (0 = follows slope exactly).
+const INERTIA: f32 = 0.05;
+/// Multiplier on how much sediment a droplet can carry.
+const SEDIMENT_CAPACITY: f32 = 4.0;
+/// Floor on the slope used for capacity, so flat ground still erodes a little.
+const MIN_SEDIMENT_CAPACITY: f32 = 0.01;
+const ERODE_SPEED: f32 = 0.3;
+const DEPOSIT_SPEED: f32 = 0.3;
+const EVAPORATE_SPEED: f32 = 0.01;
+const GRAVITY: f32 = 4.0;
+/// Radius (in cells) over which erosion is spread, to avoid pitting.
+const EROSION_RADIUS: i32 = 3;
+
+// ---------------------------------------------------------------------------
+// Thermal erosion parameters
+// ---------------------------------------------------------------------------
+
+/// Steepest stable slope (rise over run, world units). Anything steeper slumps.
+const TALUS_SLOPE: f32 = 0.8;
+/// Fraction of the excess height moved downhill per thermal iteration.
+const THERMAL_RATE: f32 = 0.5;
+
+/// Net sediment deposited per cell by the erosion pass, in world units.
+///
+/// High values mark valley floors and alluvial fans where eroded material
+/// settled. All zeros when erosion is disabled or the heightmap was imported.
+#[derive(Resource)]
+pub struct SedimentMap {
+    pub deposits: Vec<f32>,
+    pub resolution: u32,
+}
+
+impl SedimentMap {
+    pub fn get(&self, row: u32, col: u32) -> f32 {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.deposits[(row * self.resolution + col) as usize]
+    }
+}
+
+/// Small deterministic xorshift RNG so erosion is reproducible from the seed.
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// Precomputed brush of cell offsets and weights for spreading erosion.
+struct Brush {
+    offsets: Vec<(i32, i32)>,
+    weights: Vec<f32>,
+}
+
+impl Brush {
+    fn new(radius: i32) -> Self {
+        let mut offsets = Vec::new();
+        let mut weights = Vec::new();
+        for dr in -radius..=radius {
+            for dc in -radius..=radius {
+                let dist = ((dr * dr + dc * dc) as f32).sqrt();
+                if dist <= radius as f32 {
+                    offsets.push((dr, dc));
+                    weights.push(1.0 - dist / radius as f32);
+                }
+            }
+        }
+        let total: f32 = weights.iter().sum();
+        weights.iter_mut().for_each(|w| *w /= total);
+        Self { offsets, weights }
+    }
+}
+
+/// Height and gradient at a fractional grid position, by bilinear interpolation.
+fn height_and_gradient(heights: &[f32], res: usize, x: f32, z: f32) -> (f32, Vec2) {
+    let col = x as usize;
+    let row = z as usize;
+    let fx = x - col as f32;
+    let fz = z - row as f32;
+
+    let i = row * res + col;
+    let h00 = heights[i];
+    let h10 = heights[i + 1];
+    let h01 = heights[i + res];
+    let h11 = heights[i + res + 1];
+
+    let gradient = Vec2::new(
+        (h10 - h00) * (1.0 - fz) + (h11 - h01) * fz,
+        (h01 - h00) * (1.0 - fx) + (h11 - h10) * fx,
+    );
+    let height = h00 * (1.0 - fx) * (1.0 - fz)
+        + h10 * fx * (1.0 - fz)
+        + h01 * (1.0 - fx) * fz
+        + h11 * fx * fz;
+
+    (height, gradient)
+}
+
+/// Simulate `droplets` rain droplets flowing downhill, eroding where they
+/// speed up and depositing where they slow down. Heights are normalized.
+/// Returns net deposition per cell (negative where material was removed).
+fn hydraulic_erosion(heights: &mut [f32], res: usize, droplets: u32, seed: u32) -> Vec<f32> {
+    let mut deposits = vec![0.0; heights.len()];
+    let mut rng = Rng(seed | 1);
+    let brush = Brush::new(EROSION_RADIUS);
+    let max_pos = (res - 1) as f32;
+
+    for _ in 0..droplets {
+        let mut x = rng.next_f32() * (max_pos - 1.0);
+        let mut z = rng.next_f32() * (max_pos - 1.0);
+        let mut dir = Vec2::ZERO;
+        let mut speed = 1.0;
+        let mut water = 1.0;
+        let mut sediment = 0.0;
+
+        for _ in 0..DROPLET_LIFETIME {
+            let col = x as usize;
+            let row = z as usize;
+            let cell = row * res + col;
+            let fx = x - col as f32;
+            let fz = z - row as f32;
+
+            let (height, gradient) = height_and_gradient(heights, res, x, z);
+
+            dir = dir * INERTIA - gradient * (1.0 - INERTIA);
+            if dir.length_squared() < 1e-12 {
+                break;
+            }
+            dir = dir.normalize();
+            x += dir.x;
+            z += dir.y;
+
+            if x < 0.0 || z < 0.0 || x >= max_pos - 1.0 || z >= max_pos - 1.0 {
+                break;
+            }
+
+            let (new_height, _) = height_and_gradient(heights, res, x, z);
+            let delta_h = new_height - height;
+
+            let capacity = (-delta_h).max(MIN_SEDIMENT_CAPACITY) * speed * water * SEDIMENT_CAPACITY;
+
+            if sediment > capacity || delta_h > 0.0 {
+                // Uphill: fill the pit behind us. Otherwise drop excess sediment.
+                let amount = if delta_h > 0.0 {
+                    delta_h.min(sediment)
+                } else {
+                    (sediment - capacity) * DEPOSIT_SPEED
+                };
+                sediment -= amount;
+
+                // Split the deposit across the four corners of the old cell
+                let corners = [
+                    (cell, (1.0 - fx) * (1.0 - fz)),
+                    (cell + 1, fx * (1.0 - fz)),
+                    (cell + res, (1.0 - fx) * fz),
+                    (cell + res + 1, fx * fz),
+                ];
+                for (i, w) in corners {
+                    heights[i] += amount * w;
+                    deposits[i] += amount * w;
+                }
+            } else {
+                // Erode a fraction of spare capacity, never digging below the next point
+                let amount = ((capacity - sediment) * ERODE_SPEED).min(-delta_h);
+                for (&(dr, dc), &w) in brush.offsets.iter().zip(&brush.weights) {
+                    let r = row as i32 + dr;
+                    let c = col as i32 + dc;
+                    if r < 0 || c < 0 || r >= res as i32 || c >= res as i32 {
+                        continue;
+                    }
+                    let i = r as usize * res + c as usize;
+                    let removed = (amount * w).min(heights[i]);
+                    heights[i] -= removed;
+                    deposits[i] -= removed;
+                    sediment += removed;
+                }
+            }
+
+            speed = (speed * speed + delta_h * GRAVITY).max(0.0).sqrt();
+            water *= 1.0 - EVAPORATE_SPEED;
+        }
+    }
+
+    deposits
+}
+
+/// Slump material from slopes steeper than the talus angle onto their lowest
+/// neighbor, softening the cliffs left behind by hydraulic erosion.
+fn thermal_erosion(heights: &mut [f32], deposits: &mut [f32], res: usize, iterations: u32, talus: f32) {
+    let neighbors: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+    for _ in 0..iterations {
+        for row in 0..res {
+            for col in 0..res {
+                let i = row * res + col;
+
+                let mut lowest: Option<(usize, f32)> = None;
+                for &(dr, dc) in &neighbors {
+                    let r = row as i32 + dr;
+                    let c = col as i32 + dc;
+                    if r < 0 || c < 0 || r >= res as i32 || c >= res as i32 {
+                        continue;
+                    }
+                    let j = r as usize * res + c as usize;
+                    let drop = heights[i] - heights[j];
+                    if drop > talus && lowest.is_none_or(|(_, d)| drop > d) {
+                        lowest = Some((j, drop));
+                    }
+                }
+
+                if let Some((j, drop)) = lowest {
+                    let amount = (drop - talus) * THERMAL_RATE / 2.0;
+                    heights[i] -= amount;
+                    heights[j] += amount;
+                    deposits[i] -= amount;
+                    deposits[j] += amount;
+                }
+            }
+        }
+    }
+}
+
+/// Run hydraulic then thermal erosion over the heightmap, in place.
+///
+/// Runs between `generate_heightmap` and `generate_biome_map` so biomes and
+/// resources see the carved valleys. Iteration counts come from
+/// `TerrainConfig`; setting both to zero skips the pass. Successfully
+/// imported heightmaps are left untouched. Always inserts a `SedimentMap` for the resource pass.
+pub fn erode_heightmap(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    import: Res<HeightmapImport>,
+    mut heightmap: ResMut<Heightmap>,
+) {
+    let res = heightmap.resolution as usize;
+    let mut deposits = vec![0.0; res * res];
+
+    let enabled = config.erosion_droplets > 0 || config.thermal_iterations > 0;
+    if enabled && !import.loaded {
+        // Normalize so erosion constants don't depend on height_scale
+        let mut heights: Vec<f32> = heightmap
+            .heights
+            .iter()
+            .map(|h| h / config.height_scale)
+            .collect();
+
+        let mut normalized_deposits = hydraulic_erosion(
+            &mut heights,
+            res,
+            config.erosion_droplets,
+            config.derive_seed(600),
+        );
+
+        let cell_size = config.map_size / res as f32;
+        let talus = TALUS_SLOPE * cell_size / config.height_scale;
+        thermal_erosion(
+            &mut heights,
+            &mut normalized_deposits,
+            res,
+            config.thermal_iterations,
+            talus,
+        );
+
+        for (h, normalized) in heightmap.heights.iter_mut().zip(&heights) {
+            *h = normalized * config.height_scale;
+        }
+        for (d, normalized) in deposits.iter_mut().zip(&normalized_deposits) {
+            *d = normalized * config.height_scale;
+        }
+    }
+
+    commands.insert_resource(SedimentMap {
+        deposits,
+        resolution: res as u32,
+    });
+}
@@ -0,0 +1,212 @@
+use bevy::ecs::schedule::{ExecutorKind, Schedule};
+use bevy::prelude::*;
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use crate::heightmap_io::{self, HeightmapImport};
+use crate::resources::{resource_tint, ResourceDeposits, ResourceMap, ResourceType};
+use crate::terrain::{BiomeMap, GenerateWorld, Heightmap, TerrainConfig};
+use crate::notifications::Notifications;
+use crate::water::{RiverNetwork, WaterBodies, WaterBodyKind};
+
+/// Steepest rise per unit of horizontal distance between neighboring cells
+/// that still counts as buildable ground in the summary.
+const MAX_BUILDABLE_SLOPE: f32 = 0.5;
+
+/// Generate a map without a window or renderer and write its layers to
+/// `out_dir`:
+///
+/// - `heightmap.png` / `heightmap.hmap`: the final (eroded, river-carved) heights
+/// - `biomes.png`: RGB biome colors
+/// - `resources.png`: RGBA resource colors (mixed where resources overlap),
+///   alpha = richness
+/// - `summary.json`: resource coverage and deposits, water fraction and
+///   buildable area
+/// - `profile.ron`: the generation profile used, loadable with `--profile`
+///
+/// Runs the same data systems as the `GenerateWorld` schedule, minus the
+/// mesh spawners, on a bare `World`.
+pub fn generate_only(config: TerrainConfig, import: HeightmapImport, out_dir: &Path) -> io::Result<()> {
+    let mut world = World::new();
+    world.insert_resource(config);
+    world.insert_resource(import);
+    world.init_resource::<Notifications>();
+
+    let mut schedule = Schedule::new(GenerateWorld);
+    schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    schedule.add_systems(crate::world_data_systems());
+    schedule.run(&mut world);
+
+    fs::create_dir_all(out_dir)?;
+
+    let config = world.resource::<TerrainConfig>();
+    let heightmap = world.resource::<Heightmap>();
+    let biome_map = world.resource::<BiomeMap>();
+    let resource_map = world.resource::<ResourceMap>();
+    let deposits = world.resource::<ResourceDeposits>();
+    let rivers = world.resource::<RiverNetwork>();
+    let water_bodies = world.resource::<WaterBodies>();
+
+    heightmap_io::save_png(heightmap, config, &out_dir.join("heightmap.png"))?;
+    heightmap_io::save_raw(heightmap, config, &out_dir.join("heightmap.hmap"))?;
+
+    let res = heightmap.resolution;
+    let biome_pixels: Vec<u8> = biome_map
+        .biomes
+        .iter()
+        .flat_map(|biome| to_rgba8(biome.color()).into_iter().take(3))
+        .collect();
+    save_rgb_png(&out_dir.join("biomes.png"), res, png::ColorType::Rgb, &biome_pixels)?;
+
+    let resource_pixels: Vec<u8> = resource_map
+        .cells
+        .iter()
+        .flat_map(|cell| match resource_tint(cell.entries.iter()) {
+            Some(color) => to_rgba8(color),
+            None => [0; 4],
+        })
+        .collect();
+    save_rgb_png(&out_dir.join("resources.png"), res, png::ColorType::Rgba, &resource_pixels)?;
+
+    config.profile.save(&out_dir.join("profile.ron"))?;
+
+    let summary = summarize(config, heightmap, resource_map, deposits, rivers, water_bodies);
+    let file = BufWriter::new(File::create(out_dir.join("summary.json"))?);
+    serde_json::to_writer_pretty(file, &summary)?;
+
+    println!("Wrote map layers for seed {} to {}", config.seed, out_dir.display());
+    Ok(())
+}
+
+/// Resource coverage and deposits, water fraction and buildable area of a
+/// generated map.
+///
+/// A cell is buildable if it is dry (no water body or river) and no
+/// neighboring cell rises or falls more than `MAX_BUILDABLE_SLOPE` per unit
+/// of distance.
+fn summarize(
+    config: &TerrainConfig,
+    heightmap: &Heightmap,
+    resource_map: &ResourceMap,
+    deposits: &ResourceDeposits,
+    rivers: &RiverNetwork,
+    water_bodies: &WaterBodies,
+) -> serde_json::Value {
+    let res = heightmap.resolution;
+    let cell_count = (res * res) as f32;
+    let cell_size = config.map_size / res as f32;
+    let cell_area = cell_size * cell_size;
+
+    let mut water_cells = 0u32;
+    let mut buildable_cells = 0u32;
+    for row in 0..res {
+        for col in 0..res {
+            if water_bodies.is_water(row, col) || rivers.is_river(row, col) {
+                water_cells += 1;
+                continue;
+            }
+            let height = heightmap.get(row, col);
+            let neighbors = [
+                (row.saturating_sub(1), col),
+                (row + 1, col),
+                (row, col.saturating_sub(1)),
+                (row, col + 1),
+            ];
+            let steepest = neighbors
+                .iter()
+                .map(|&(r, c)| (heightmap.get(r, c) - height).abs())
+                .fold(0.0f32, f32::max);
+            if steepest / cell_size <= MAX_BUILDABLE_SLOPE {
+                buildable_cells += 1;
+            }
+        }
+    }
+
+    let mut coverage = serde_json::Map::new();
+    for resource in ResourceType::ALL {
+        let richness: Vec<f32> = resource_map
+            .cells
+            .iter()
+            .filter_map(|cell| cell.get(resource))
+            .map(|entry| entry.richness)
+            .collect();
+        let cells = richness.len();
+        let mean_richness = if cells > 0 {
+            richness.iter().sum::<f32>() / cells as f32
+        } else {
+            0.0
+        };
+        let deposit_count = deposits
+            .deposits
+            .iter()
+            .filter(|deposit| deposit.resource == resource)
+            .count();
+        coverage.insert(
+            resource.label().to_string(),
+            json!({
+                "cells": cells,
+                "fraction": cells as f32 / cell_count,
+                "mean_richness": mean_richness,
+                "deposits": deposit_count,
+            }),
+        );
+    }
+
+    let deposit_list: Vec<serde_json::Value> = deposits
+        .deposits
+        .iter()
+        .map(|deposit| {
+            json!({
+                "id": deposit.id.0,
+                "resource": deposit.resource.label(),
+                "cells": deposit.cells.len(),
+                "total_reserves": deposit.total_reserves,
+                "centroid": [deposit.centroid.x, deposit.centroid.y],
+                "bounds": {
+                    "min_row": deposit.bounds.min_row,
+                    "min_col": deposit.bounds.min_col,
+                    "max_row": deposit.bounds.max_row,
+                    "max_col": deposit.bounds.max_col,
+                },
+            })
+        })
+        .collect();
+
+    let lakes = water_bodies
+        .bodies
+        .iter()
+        .filter(|body| body.kind == WaterBodyKind::Lake)
+        .count();
+
+    json!({
+        "seed": config.seed,
+        "profile": config.profile.name,
+        "resolution": res,
+        "map_size": config.map_size,
+        "water_fraction": water_cells as f32 / cell_count,
+        "buildable_fraction": buildable_cells as f32 / cell_count,
+        "buildable_area": buildable_cells as f32 * cell_area,
+        "rivers": rivers.rivers.len(),
+        "lakes": lakes,
+        "resource_coverage": coverage,
+        "deposits": deposit_list,
+    })
+}
+
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Write an 8-bit RGB or RGBA PNG.
+fn save_rgb_png(path: &Path, resolution: u32, color_type: png::ColorType, pixels: &[u8]) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, resolution, resolution);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,396 @@
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::terrain::{noise_heightmap, Heightmap, TerrainConfig};
+use crate::notifications::Notifications;
+
+/// Magic bytes at the start of a raw heightmap file.
+const RAW_MAGIC: &[u8; 4] = b"GHM1";
+
+/// Heightmap file to load at startup instead of generating one from noise.
+/// Set from the `--heightmap <path>` command line argument.
+#[derive(Resource, Default)]
+pub struct HeightmapImport {
+    pub path: Option<PathBuf>,
+    /// Whether the last world generation used the file. False if it could
+    /// not be read and noise was generated instead.
+    pub loaded: bool,
+}
+
+/// A heightmap read from disk, along with the terrain dimensions stored
+/// alongside it. PNGs written by other tools carry no dimensions, so
+/// `map_size` and `height_scale` are optional.
+pub struct LoadedHeightmap {
+    pub heightmap: Heightmap,
+    pub map_size: Option<f32>,
+    pub height_scale: Option<f32>,
+}
+
+// ---------------------------------------------------------------------------
+// 16-bit grayscale PNG
+// ---------------------------------------------------------------------------
+
+/// Write the heightmap as a 16-bit grayscale PNG. Heights are normalized from
+/// 0..`height_scale` to the full 16-bit range. `map_size` and `height_scale`
+/// are stored in tEXt chunks so the map round-trips at the same dimensions.
+pub fn save_png(heightmap: &Heightmap, config: &TerrainConfig, path: &Path) -> io::Result<()> {
+    let res = heightmap.resolution;
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, res, res);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    encoder.add_text_chunk("map_size".to_string(), config.map_size.to_string())?;
+    encoder.add_text_chunk("height_scale".to_string(), config.height_scale.to_string())?;
+
+    // PNG stores 16-bit samples big-endian
+    let mut data = Vec::with_capacity(heightmap.heights.len() * 2);
+    for &h in &heightmap.heights {
+        let t = (h / config.height_scale).clamp(0.0, 1.0);
+        let sample = (t * u16::MAX as f32).round() as u16;
+        data.extend_from_slice(&sample.to_be_bytes());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Read a square grayscale PNG (8 or 16 bit) as a heightmap.
+///
+/// Heights are returned normalized to 0.0–1.0 scaled by the `height_scale`
+/// tEXt chunk if present, otherwise left in 0.0–1.0 for the caller to scale.
+pub fn load_png(path: &Path) -> io::Result<LoadedHeightmap> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let mut reader = decoder.read_info()?;
+
+    let info = reader.info();
+    let (width, height) = (info.width, info.height);
+    if width != height {
+        return Err(invalid_data(format!("heightmap must be square, got {width}x{height}")));
+    }
+    if width < 2 {
+        return Err(invalid_data(format!("heightmap must be at least 2x2, got {width}x{height}")));
+    }
+    if info.color_type != png::ColorType::Grayscale {
+        return Err(invalid_data("heightmap PNG must be grayscale"));
+    }
+
+    let text_value = |key: &str| {
+        info.uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == key)
+            .and_then(|chunk| chunk.text.trim().parse::<f32>().ok())
+    };
+    let map_size = text_value("map_size").map(|v| positive_dimension("map_size", v)).transpose()?;
+    let height_scale = text_value("height_scale").map(|v| positive_dimension("height_scale", v)).transpose()?;
+    let bit_depth = info.bit_depth;
+
+    let buffer_size = reader
+        .output_buffer_size()
+        .ok_or_else(|| invalid_data("heightmap PNG is too large"))?;
+    let mut buf = vec![0; buffer_size];
+    let frame = reader.next_frame(&mut buf)?;
+    let bytes = &buf[..frame.buffer_size()];
+
+    let normalized: Vec<f32> = match bit_depth {
+        png::BitDepth::Sixteen => bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / u16::MAX as f32)
+            .collect(),
+        png::BitDepth::Eight => bytes.iter().map(|&b| b as f32 / u8::MAX as f32).collect(),
+        other => return Err(invalid_data(format!("unsupported PNG bit depth {other:?}"))),
+    };
+
+    let scale = height_scale.unwrap_or(1.0);
+    Ok(LoadedHeightmap {
+        heightmap: Heightmap {
+            heights: normalized.into_iter().map(|t| t * scale).collect(),
+            resolution: width,
+        },
+        map_size,
+        height_scale,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Raw little-endian f32
+// ---------------------------------------------------------------------------
+
+/// Write the heightmap as raw little-endian f32 with a small header:
+///
+/// | bytes | field |
+/// |---|---|
+/// | 4 | magic `GHM1` |
+/// | 4 | `resolution` (u32) |
+/// | 4 | `map_size` (f32) |
+/// | 4 | `height_scale` (f32) |
+/// | 4 × resolution² | heights, row-major |
+pub fn save_raw(heightmap: &Heightmap, config: &TerrainConfig, path: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(RAW_MAGIC)?;
+    file.write_all(&heightmap.resolution.to_le_bytes())?;
+    file.write_all(&config.map_size.to_le_bytes())?;
+    file.write_all(&config.height_scale.to_le_bytes())?;
+    for &h in &heightmap.heights {
+        file.write_all(&h.to_le_bytes())?;
+    }
+    file.flush()
+}
+
+/// Read a raw heightmap written by [`save_raw`].
+pub fn load_raw(path: &Path) -> io::Result<LoadedHeightmap> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    if bytes.len() < 16 || &bytes[0..4] != RAW_MAGIC {
+        return Err(invalid_data("not a raw heightmap file (bad header)"));
+    }
+    let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+    let resolution = u32::from_le_bytes(word(4));
+    let map_size = positive_dimension("map_size", f32::from_le_bytes(word(8)))?;
+    let height_scale = positive_dimension("height_scale", f32::from_le_bytes(word(12)))?;
+
+    // A hostile header can overflow the byte count
+    let expected_len = (resolution as usize)
+        .checked_mul(resolution as usize)
+        .and_then(|count| count.checked_mul(4));
+    let body = &bytes[16..];
+    if resolution < 2 || expected_len != Some(body.len()) {
+        return Err(invalid_data(format!(
+            "expected {resolution}x{resolution} heights, found {} bytes",
+            body.len()
+        )));
+    }
+
+    let heights = body
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    Ok(LoadedHeightmap {
+        heightmap: Heightmap { heights, resolution },
+        map_size: Some(map_size),
+        height_scale: Some(height_scale),
+    })
+}
+
+/// Load a heightmap, choosing the format from the file extension
+/// (`.png` for PNG, anything else is treated as raw f32).
+pub fn load(path: &Path) -> io::Result<LoadedHeightmap> {
+    let is_png = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    if is_png { load_png(path) } else { load_raw(path) }
+}
+
+/// Reject a map dimension that is not a finite, positive number.
+fn positive_dimension(name: &str, value: f32) -> io::Result<f32> {
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(invalid_data(format!("{name} must be a positive number, got {value}")))
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Run condition: a heightmap file was requested on the command line.
+pub fn import_requested(import: Res<HeightmapImport>) -> bool {
+    import.path.is_some()
+}
+
+/// Load the heightmap from `HeightmapImport` in place of `generate_heightmap`.
+///
+/// The file's resolution, map size and height scale override `TerrainConfig`
+/// so the biome and resource passes run at the imported dimensions. PNGs
+/// without a `height_scale` chunk keep the configured scale. Falls back to
+/// noise generation if the file cannot be read, leaving `loaded` false so
+/// the fallback is eroded like any generated map.
+pub fn load_heightmap(
+    mut commands: Commands,
+    mut import: ResMut<HeightmapImport>,
+    mut config: ResMut<TerrainConfig>,
+    mut notifications: ResMut<Notifications>,
+) {
+    let Some(path) = import.path.clone() else {
+        return;
+    };
+
+    let result = load(&path);
+    import.loaded = result.is_ok();
+    match result {
+        Ok(loaded) => {
+            let mut heightmap = loaded.heightmap;
+            match loaded.height_scale {
+                Some(scale) => config.height_scale = scale,
+                None => heightmap.heights.iter_mut().for_each(|h| *h *= config.height_scale),
+            }
+            if let Some(map_size) = loaded.map_size {
+                config.map_size = map_size;
+            }
+            config.resolution = heightmap.resolution;
+            commands.insert_resource(heightmap);
+        }
+        Err(err) => {
+            error!("Failed to load heightmap {}: {err}", path.display());
+            notifications.push(format!("Could not load {}", path.display()), 5.0);
+            commands.insert_resource(noise_heightmap(&config));
+        }
+    }
+}
+
+/// Write the current heightmap to the working directory as
+/// `heightmap_<seed>.png` and `heightmap_<seed>.hmap`.
+#[cfg(feature = "gui")]
+pub fn export_heightmap(
+    heightmap: &Heightmap,
+    config: &TerrainConfig,
+    notifications: &mut Notifications,
+) {
+    let png_path = PathBuf::from(format!("heightmap_{}.png", config.seed));
+    let raw_path = PathBuf::from(format!("heightmap_{}.hmap", config.seed));
+
+    let result = save_png(heightmap, config, &png_path)
+        .and_then(|_| save_raw(heightmap, config, &raw_path));
+    match result {
+        Ok(()) => notifications.push(
+            format!("Exported {} and {}", png_path.display(), raw_path.display()),
+            4.0,
+        ),
+        Err(err) => {
+            error!("Heightmap export failed: {err}");
+            notifications.push("Heightmap export failed", 4.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the system temp dir, unique to this test process.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("heightmap_io_{}_{name}", std::process::id()))
+    }
+
+    fn fs_cleanup(path: &Path) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// 17x17 ramp with a bump, spanning 0..`height_scale`.
+    fn sample_heightmap(height_scale: f32) -> Heightmap {
+        let res = 17;
+        let heights = (0..res * res)
+            .map(|i| {
+                let (row, col) = ((i / res) as f32, (i % res) as f32);
+                let t = (row + col) / (2.0 * (res - 1) as f32);
+                (t + 0.1 * (row * 0.7).sin()).clamp(0.0, 1.0) * height_scale
+            })
+            .collect();
+        Heightmap { heights, resolution: res }
+    }
+
+    fn config(map_size: f32, height_scale: f32) -> TerrainConfig {
+        TerrainConfig {
+            map_size,
+            height_scale,
+            ..default()
+        }
+    }
+
+    fn assert_invalid_data(result: io::Result<LoadedHeightmap>) {
+        match result {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{err}"),
+            Ok(_) => panic!("expected the file to be rejected"),
+        }
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let config = config(300.0, 40.0);
+        let heightmap = sample_heightmap(config.height_scale);
+        let path = temp_path("round_trip.png");
+        save_png(&heightmap, &config, &path).unwrap();
+        let loaded = load(&path).unwrap();
+        fs_cleanup(&path);
+
+        assert_eq!(loaded.map_size, Some(300.0));
+        assert_eq!(loaded.height_scale, Some(40.0));
+        assert_eq!(loaded.heightmap.resolution, heightmap.resolution);
+        // Within one 16-bit step
+        let step = config.height_scale / u16::MAX as f32;
+        for (a, b) in heightmap.heights.iter().zip(&loaded.heightmap.heights) {
+            assert!((a - b).abs() <= step, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let config = config(512.0, 60.0);
+        let heightmap = sample_heightmap(config.height_scale);
+        let path = temp_path("round_trip.hmap");
+        save_raw(&heightmap, &config, &path).unwrap();
+        let loaded = load(&path).unwrap();
+        fs_cleanup(&path);
+
+        assert_eq!(loaded.map_size, Some(512.0));
+        assert_eq!(loaded.height_scale, Some(60.0));
+        assert_eq!(loaded.heightmap.resolution, heightmap.resolution);
+        assert_eq!(loaded.heightmap.heights, heightmap.heights);
+    }
+
+    #[test]
+    fn rejects_tiny_png() {
+        let config = config(300.0, 40.0);
+        let heightmap = Heightmap {
+            heights: vec![10.0],
+            resolution: 1,
+        };
+        let path = temp_path("tiny.png");
+        save_png(&heightmap, &config, &path).unwrap();
+        let result = load_png(&path);
+        fs_cleanup(&path);
+        assert_invalid_data(result);
+    }
+
+    #[test]
+    fn rejects_non_positive_map_size() {
+        let heightmap = sample_heightmap(40.0);
+        for (i, map_size) in [0.0, -100.0, f32::NAN].into_iter().enumerate() {
+            let config = config(map_size, 40.0);
+            let png = temp_path(&format!("bad_size_{i}.png"));
+            let raw = temp_path(&format!("bad_size_{i}.hmap"));
+            save_png(&heightmap, &config, &png).unwrap();
+            save_raw(&heightmap, &config, &raw).unwrap();
+            let (from_png, from_raw) = (load_png(&png), load_raw(&raw));
+            fs_cleanup(&png);
+            fs_cleanup(&raw);
+            assert_invalid_data(from_png);
+            assert_invalid_data(from_raw);
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_raw_header() {
+        let mut bytes = RAW_MAGIC.to_vec();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&300.0f32.to_le_bytes());
+        bytes.extend_from_slice(&40.0f32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        let path = temp_path("overflow.hmap");
+        std::fs::write(&path, bytes).unwrap();
+        let result = load_raw(&path);
+        fs_cleanup(&path);
+        assert_invalid_data(result);
+    }
+}
@@ -1,15 +1,69 @@
+use bevy::ecs::schedule::ScheduleConfigs;
+use bevy::ecs::system::ScheduleSystem;
 use bevy::prelude::*;
+#[cfg(feature = "gui")]
 use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
 
+#[cfg(feature = "gui")]
 mod building;
+#[cfg(feature = "gui")]
+mod bulldoze;
+#[cfg(feature = "gui")]
 mod camera;
+mod climate;
+#[cfg(feature = "gui")]
 mod economy;
+mod erosion;
+mod headless;
+mod heightmap_io;
+mod notifications;
+#[cfg(any(feature = "gui", test))]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+mod pathfinding;
+mod profile;
 mod resources;
+#[cfg(any(feature = "gui", test))]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 mod road;
+#[cfg(feature = "gui")]
+mod road_upgrade;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+mod spatial;
+#[cfg(feature = "gui")]
+mod survey;
+#[cfg(feature = "gui")]
+mod terraform;
 mod terrain;
+#[cfg(feature = "gui")]
 mod ui;
+mod water;
 
 fn main() {
+    let terrain_config = terrain::TerrainConfig {
+        seed: parse_seed_arg().unwrap_or(42),
+        profile: parse_profile_arg().unwrap_or_default(),
+        ..default()
+    };
+    let heightmap_import = heightmap_io::HeightmapImport {
+        path: arg_value("--heightmap").map(std::path::PathBuf::from),
+        ..default()
+    };
+
+    if std::env::args().any(|a| a == "--generate-only") {
+        let out_dir = arg_value("--out").unwrap_or_else(|| "map_out".to_string());
+        if let Err(err) = headless::generate_only(terrain_config, heightmap_import, out_dir.as_ref()) {
+            eprintln!("Map generation failed: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    run_game(terrain_config, heightmap_import);
+}
+
+/// Open the game window and run the city builder.
+#[cfg(feature = "gui")]
+fn run_game(terrain_config: terrain::TerrainConfig, heightmap_import: heightmap_io::HeightmapImport) {
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -20,34 +74,201 @@ fn main() {
             ..default()
         }))
         .add_plugins(EguiPlugin::default())
-        .init_resource::<terrain::TerrainConfig>()
+        .insert_resource(terrain_config)
+        .insert_resource(heightmap_import)
         .init_resource::<road::RoadNetwork>()
+        .init_resource::<road::RoadMeshes>()
         .init_resource::<road::ActiveTool>()
         .init_resource::<road::RoadPlacementState>()
         .init_resource::<ui::GameTime>()
         .init_resource::<ui::CursorWorldPosition>()
-        .init_resource::<ui::Notifications>()
+        .init_resource::<notifications::Notifications>()
         .init_resource::<economy::EconomyDebug>()
         .init_resource::<ui::DebugVisible>()
+        .init_resource::<resources::ResourceOverlay>()
         .init_resource::<building::SpawnTimer>()
-        .add_systems(Startup, (terrain::generate_heightmap, terrain::generate_biome_map, resources::generate_resource_map, terrain::spawn_terrain_mesh, terrain::spawn_water_plane, setup).chain())
+        .init_resource::<economy::ExtractionTimer>()
+        .init_resource::<ui::NewGameDialog>()
+        .init_resource::<NewGameRequest>()
+        .init_resource::<terraform::TerraformBrush>()
+        .init_resource::<terraform::TerrainEdits>()
+        .init_resource::<ui::CityBudget>()
+        .init_resource::<road_upgrade::RoadUpgrades>()
+        .init_resource::<building::LotIndex>()
+        .add_observer(building::index_added_lot)
+        .add_observer(building::unindex_removed_lot)
+        .add_systems(terrain::GenerateWorld, (
+            world_data_systems(),
+            terrain::spawn_terrain_mesh,
+            resources::spawn_resource_overlay,
+            water::spawn_water_bodies,
+            water::spawn_river_meshes,
+        ).chain())
+        .add_systems(Startup, (terrain::generate_world, setup).chain())
         .add_systems(Update, (
+            start_new_game,
             camera::camera_controls,
+            terrain::update_terrain_lod,
             ui::speed_controls,
             ui::update_cursor_position,
-            ui::tick_notifications,
-            road::toggle_road_tool,
-            road::road_placement_input,
-            road::generate_road_meshes,
-            road::draw_road_debug,
-            economy::evaluate_and_spawn,
-            resources::draw_resource_debug,
+            notifications::tick_notifications,
+            (
+                road::toggle_road_tool,
+                road::road_placement_input,
+                road::generate_road_meshes,
+                road::draw_road_debug,
+            ),
+            terraform::toggle_terraform_tool,
+            (terraform::terraform_input, terraform::apply_terrain_edits).chain(),
+            terraform::draw_terraform_brush,
+            (survey::toggle_survey_tool, survey::draw_survey_preview),
+            (survey::survey_input, economy::extract_resources, survey::recolor_surveyed_chunks).chain(),
+            (bulldoze::toggle_bulldoze_tool, bulldoze::bulldoze_input, bulldoze::draw_bulldoze_highlight),
+            (
+                road_upgrade::toggle_upgrade_tool,
+                road_upgrade::upgrade_input,
+                road_upgrade::advance_road_upgrades,
+                road_upgrade::draw_road_upgrades,
+            ),
+            (building::refresh_frontage, economy::evaluate_and_spawn).chain(),
+            building::abandon_disconnected,
+            resources::update_resource_overlay,
             building::draw_lot_debug,
         ))
-        .add_systems(EguiPrimaryContextPass, ui::draw_ui)
+        .add_systems(EguiPrimaryContextPass, (
+            ui::draw_ui,
+            ui::draw_info_panel,
+            ui::draw_resource_filter,
+            terraform::draw_terraform_panel,
+        ).chain())
         .run();
 }
 
+/// Without the `gui` feature there is no game to run, only map generation.
+#[cfg(not(feature = "gui"))]
+fn run_game(_: terrain::TerrainConfig, _: heightmap_io::HeightmapImport) {
+    eprintln!("Built without the `gui` feature: only --generate-only is available");
+    std::process::exit(2);
+}
+
+/// The data half of world generation (heightmap → erosion → water → climate
+/// → biomes → resources → deposits), without any mesh spawning. Shared by the
+/// `GenerateWorld` schedule and `--generate-only` headless mode.
+pub fn world_data_systems() -> ScheduleConfigs<ScheduleSystem> {
+    (
+        terrain::generate_heightmap.run_if(not(heightmap_io::import_requested)),
+        heightmap_io::load_heightmap.run_if(heightmap_io::import_requested),
+        erosion::erode_heightmap,
+        water::generate_rivers,
+        water::generate_water_bodies,
+        climate::generate_climate_map,
+        terrain::generate_biome_map,
+        resources::generate_resource_map,
+        resources::generate_resource_deposits,
+    ).chain()
+}
+
+/// Value following a `--flag` on the command line, if present.
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != flag);
+    args.next()?;
+    args.next()
+}
+
+/// Read `--seed <n>` from the command line, if present.
+fn parse_seed_arg() -> Option<u32> {
+    let value = arg_value("--seed")?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            eprintln!("Ignoring invalid --seed value: {value}");
+            None
+        }
+    }
+}
+
+/// Read `--profile <preset name | path.ron>` from the command line, if present.
+fn parse_profile_arg() -> Option<profile::GenerationProfile> {
+    let value = arg_value("--profile")?;
+    if let Some(preset) = profile::GenerationProfile::preset(&value) {
+        return Some(preset);
+    }
+    match profile::GenerationProfile::load(std::path::Path::new(&value)) {
+        Ok(profile) => Some(profile),
+        Err(err) => {
+            eprintln!("Ignoring --profile {value}: {err}");
+            None
+        }
+    }
+}
+
+/// A world regeneration requested from the new-game dialog.
+#[cfg(feature = "gui")]
+pub struct NewGame {
+    pub seed: u32,
+    pub profile: profile::GenerationProfile,
+}
+
+/// Pending new game, consumed by `start_new_game`.
+#[cfg(feature = "gui")]
+#[derive(Resource, Default)]
+pub struct NewGameRequest(pub Option<NewGame>);
+
+/// Entities that belong to the current map and are discarded on a new game.
+#[cfg(feature = "gui")]
+type GeneratedWorldFilter = Or<(
+    With<terrain::TerrainMesh>,
+    With<terrain::WaterSurface>,
+    With<road::RoadMesh>,
+    With<building::Building>,
+    With<building::Lot>,
+)>;
+
+/// Tear down the current world and regenerate it from a new seed and profile.
+///
+/// Despawns terrain, water, road meshes and buildings, resets the road network,
+/// funds and economy state, then reruns the `GenerateWorld` schedule.
+#[cfg(feature = "gui")]
+#[allow(clippy::too_many_arguments)]
+fn start_new_game(
+    mut commands: Commands,
+    mut request: ResMut<NewGameRequest>,
+    mut config: ResMut<terrain::TerrainConfig>,
+    mut road_network: ResMut<road::RoadNetwork>,
+    mut road_meshes: ResMut<road::RoadMeshes>,
+    mut placement: ResMut<road::RoadPlacementState>,
+    mut economy_debug: ResMut<economy::EconomyDebug>,
+    mut spawn_timer: ResMut<building::SpawnTimer>,
+    mut extraction_timer: ResMut<economy::ExtractionTimer>,
+    mut budget: ResMut<ui::CityBudget>,
+    mut road_upgrades: ResMut<road_upgrade::RoadUpgrades>,
+    mut notifications: ResMut<notifications::Notifications>,
+    world_entities: Query<Entity, GeneratedWorldFilter>,
+) {
+    let Some(NewGame { seed, profile }) = request.0.take() else {
+        return;
+    };
+
+    for entity in &world_entities {
+        commands.entity(entity).despawn();
+    }
+
+    config.seed = seed;
+    config.profile = profile;
+    *road_network = default();
+    *road_meshes = default();
+    placement.points.clear();
+    *economy_debug = default();
+    *spawn_timer = default();
+    *extraction_timer = default();
+    *budget = default();
+    *road_upgrades = default();
+
+    commands.run_schedule(terrain::GenerateWorld);
+    notifications.push(format!("New game (seed {}, {})", seed, config.profile.name), 3.0);
+}
+
+#[cfg(feature = "gui")]
 fn setup(mut commands: Commands) {
     // Directional light (sun)
     commands.spawn((
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+/// A timed notification message.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct Notification {
+    pub message: String,
+    pub timer: f32,
+}
+
+/// Queue of notification messages displayed to the player.
+#[derive(Resource, Default)]
+pub struct Notifications {
+    pub messages: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, message: impl Into<String>, duration: f32) {
+        self.messages.push(Notification {
+            message: message.into(),
+            timer: duration,
+        });
+    }
+}
+
+/// Tick down notification timers and remove expired ones.
+#[cfg(feature = "gui")]
+pub fn tick_notifications(time: Res<Time>, mut notifications: ResMut<Notifications>) {
+    let dt = time.delta_secs();
+    for notif in &mut notifications.messages {
+        notif.timer -= dt;
+    }
+    notifications.messages.retain(|n| n.timer > 0.0);
+}
@@ -0,0 +1,406 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::road::{NodeId, RoadNetwork, RoadType, SegmentId};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// World units travelled per second on a dirt road. Other surfaces scale
+/// this by `RoadType::speed_factor`.
+pub const DIRT_ROAD_SPEED: f32 = 8.0;
+
+/// Fastest surface, used to keep the A* heuristic from overestimating.
+const FASTEST_ROAD: RoadType = RoadType::Paved;
+
+// ---------------------------------------------------------------------------
+// Results
+// ---------------------------------------------------------------------------
+
+/// A route through the road network.
+#[derive(Debug)]
+pub struct RoadPath {
+    /// Nodes visited, starting at the origin and ending at the destination.
+    pub nodes: Vec<NodeId>,
+    /// Segments driven, one fewer than `nodes`.
+    pub segments: Vec<SegmentId>,
+    /// Total spline length in world units.
+    pub length: f32,
+    /// Total travel time in seconds.
+    pub travel_time: f32,
+}
+
+/// Best known way of reaching a node during a search.
+#[derive(Clone, Copy)]
+struct Reach {
+    travel_time: f32,
+    length: f32,
+    /// Segment and node this node was reached from. `None` for a source.
+    via: Option<(SegmentId, NodeId)>,
+}
+
+/// Travel times from one or more sources to every node reached within the
+/// search limit. Returned by `RoadNetwork::travel_times_from` and
+/// `travel_times_from_point`.
+pub struct TravelTimes {
+    reached: HashMap<NodeId, Reach>,
+    max_time: f32,
+    /// Segment and fraction along it the search started from, if it started
+    /// partway along a segment rather than at nodes.
+    origin: Option<(SegmentId, f32)>,
+}
+
+impl TravelTimes {
+    /// Travel time in seconds to a node, or `None` if it was not reached.
+    pub fn time_to(&self, node: NodeId) -> Option<f32> {
+        self.reached.get(&node).map(|reach| reach.travel_time)
+    }
+
+    /// Travel time in seconds to the point a fraction `t` (0..1 by length)
+    /// along `segment`, or `None` if it lies beyond the search limit.
+    pub fn time_to_point(&self, network: &RoadNetwork, segment: SegmentId, t: f32) -> Option<f32> {
+        let segment_time = network.segment_travel_time(segment)?;
+        let nodes = network.segment(segment)?.nodes;
+
+        let mut best = f32::INFINITY;
+        if let Some(time) = self.time_to(nodes[0]) {
+            best = best.min(time + t * segment_time);
+        }
+        if let Some(time) = self.time_to(nodes[1]) {
+            best = best.min(time + (1.0 - t) * segment_time);
+        }
+        // Both points on the same segment: drive straight along it
+        if let Some((origin_segment, origin_t)) = self.origin
+            && origin_segment == segment
+        {
+            best = best.min((t - origin_t).abs() * segment_time);
+        }
+
+        (best <= self.max_time).then_some(best)
+    }
+
+    /// Every reached node with its travel time.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, f32)> + '_ {
+        self.reached.iter().map(|(&id, reach)| (id, reach.travel_time))
+    }
+
+    /// Fastest route from the nearest source to a reached node.
+    pub fn path_to(&self, node: NodeId) -> Option<RoadPath> {
+        trace_path(&self.reached, node)
+    }
+}
+
+/// Min-heap entry ordered by ascending priority.
+struct Frontier {
+    priority: f32,
+    node: NodeId,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Searches
+// ---------------------------------------------------------------------------
+
+impl RoadNetwork {
+    /// Seconds to drive a segment end to end: spline length over the speed of
+    /// its road type.
+    pub fn segment_travel_time(&self, id: SegmentId) -> Option<f32> {
+        let segment = self.segment(id)?;
+        Some(travel_time(self.segment_length(id)?, segment.road_type))
+    }
+
+    /// Fastest route between two nodes (A*), or `None` if `to` cannot be
+    /// reached from `from`.
+    pub fn find_path(&self, from: NodeId, to: NodeId) -> Option<RoadPath> {
+        let goal = self.node(to)?.position;
+        let top_speed = DIRT_ROAD_SPEED * FASTEST_ROAD.speed_factor();
+        let reached = self.search(&[(from, 0.0)], Some(to), f32::INFINITY, |position| {
+            position.distance(goal) / top_speed
+        });
+        trace_path(&reached, to)
+    }
+
+    /// Travel times from the given sources to every node reachable within
+    /// `max_time` seconds (Dijkstra). Each source starts with its own time
+    /// already elapsed, so a search can begin partway along a segment by
+    /// seeding both of its endpoints.
+    pub fn travel_times_from(&self, sources: &[(NodeId, f32)], max_time: f32) -> TravelTimes {
+        TravelTimes {
+            reached: self.search(sources, None, max_time, |_| 0.0),
+            max_time,
+            origin: None,
+        }
+    }
+
+    /// Isochrone around a point a fraction `t` (0..1 by length) along a
+    /// segment, such as a building's frontage: travel times to every node and
+    /// road point reachable within `max_time` seconds.
+    pub fn travel_times_from_point(&self, segment: SegmentId, t: f32, max_time: f32) -> TravelTimes {
+        let sources = match (self.segment(segment), self.segment_travel_time(segment)) {
+            (Some(seg), Some(segment_time)) => {
+                vec![(seg.nodes[0], t * segment_time), (seg.nodes[1], (1.0 - t) * segment_time)]
+            }
+            _ => Vec::new(),
+        };
+        TravelTimes {
+            reached: self.search(&sources, None, max_time, |_| 0.0),
+            max_time,
+            origin: Some((segment, t)),
+        }
+    }
+
+    /// Shared best-first search. Expands nodes in order of travel time plus
+    /// `heuristic`, stopping at `target` if given, and never settles a node
+    /// costing more than `max_time`.
+    fn search(
+        &self,
+        sources: &[(NodeId, f32)],
+        target: Option<NodeId>,
+        max_time: f32,
+        heuristic: impl Fn(Vec3) -> f32,
+    ) -> HashMap<NodeId, Reach> {
+        let mut best: HashMap<NodeId, Reach> = HashMap::new();
+        let mut settled: HashMap<NodeId, Reach> = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+
+        for &(id, time) in sources {
+            let Some(node) = self.node(id) else { continue };
+            if time > max_time || best.get(&id).is_some_and(|reach| reach.travel_time <= time) {
+                continue;
+            }
+            best.insert(id, Reach { travel_time: time, length: 0.0, via: None });
+            frontier.push(Frontier { priority: time + heuristic(node.position), node: id });
+        }
+
+        while let Some(Frontier { node: id, .. }) = frontier.pop() {
+            if settled.contains_key(&id) {
+                continue;
+            }
+            let reach = best[&id];
+            settled.insert(id, reach);
+            if target == Some(id) {
+                break;
+            }
+
+            let Some(node) = self.node(id) else { continue };
+            for &segment_id in &node.segments {
+                let Some(segment) = self.segment(segment_id) else { continue };
+                let next_id = if segment.nodes[0] == id { segment.nodes[1] } else { segment.nodes[0] };
+                if next_id == id || settled.contains_key(&next_id) {
+                    continue;
+                }
+                let (Some(next), Some(length)) = (self.node(next_id), self.segment_length(segment_id)) else {
+                    continue;
+                };
+
+                let time = reach.travel_time + travel_time(length, segment.road_type);
+                if time > max_time || best.get(&next_id).is_some_and(|r| r.travel_time <= time) {
+                    continue;
+                }
+                best.insert(next_id, Reach {
+                    travel_time: time,
+                    length: reach.length + length,
+                    via: Some((segment_id, id)),
+                });
+                frontier.push(Frontier {
+                    priority: time + heuristic(next.position),
+                    node: next_id,
+                });
+            }
+        }
+
+        settled
+    }
+}
+
+/// Seconds to drive `length` world units on a road of the given type.
+fn travel_time(length: f32, road_type: RoadType) -> f32 {
+    length / (DIRT_ROAD_SPEED * road_type.speed_factor())
+}
+
+/// Walk predecessor links back from `node` to its source.
+fn trace_path(reached: &HashMap<NodeId, Reach>, node: NodeId) -> Option<RoadPath> {
+    let end = reached.get(&node)?;
+    let mut nodes = vec![node];
+    let mut segments = Vec::new();
+    let mut current = *end;
+    while let Some((segment, previous)) = current.via {
+        segments.push(segment);
+        nodes.push(previous);
+        current = reached[&previous];
+    }
+    nodes.reverse();
+    segments.reverse();
+
+    Some(RoadPath {
+        nodes,
+        segments,
+        length: end.length - current.length,
+        travel_time: end.travel_time - current.travel_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Add a straight segment of the given type between two nodes.
+    fn road(network: &mut RoadNetwork, a: NodeId, b: NodeId, road_type: RoadType) -> SegmentId {
+        network.add_segment(a, b, Vec::new(), road_type, 2.0)
+    }
+
+    #[test]
+    fn straight_chain() {
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(40.0, 0.0, 0.0));
+        let c = network.add_node(Vec3::new(40.0, 0.0, 80.0));
+        let ab = road(&mut network, a, b, RoadType::Dirt);
+        let bc = road(&mut network, b, c, RoadType::Dirt);
+
+        let path = network.find_path(a, c).unwrap();
+        assert_eq!(path.nodes, vec![a, b, c]);
+        assert_eq!(path.segments, vec![ab, bc]);
+        assert!((path.length - 120.0).abs() < 0.01);
+        assert!((path.travel_time - 120.0 / DIRT_ROAD_SPEED).abs() < 0.01);
+    }
+
+    #[test]
+    fn path_to_self_is_empty() {
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::ZERO);
+
+        let path = network.find_path(a, a).unwrap();
+        assert_eq!(path.nodes, vec![a]);
+        assert!(path.segments.is_empty());
+        assert_eq!(path.travel_time, 0.0);
+    }
+
+    #[test]
+    fn prefers_faster_road_over_shorter_one() {
+        // Direct dirt road of 100 units vs. a 120-unit paved detour, which
+        // takes 48 dirt-equivalent units of time.
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let detour = network.add_node(Vec3::new(50.0, 0.0, (60.0f32 * 60.0 - 50.0 * 50.0).sqrt()));
+        road(&mut network, a, b, RoadType::Dirt);
+        road(&mut network, a, detour, RoadType::Paved);
+        road(&mut network, detour, b, RoadType::Paved);
+
+        let path = network.find_path(a, b).unwrap();
+        assert_eq!(path.nodes, vec![a, detour, b]);
+        assert!((path.length - 120.0).abs() < 0.01);
+        assert!((path.travel_time - 48.0 / DIRT_ROAD_SPEED).abs() < 0.01);
+    }
+
+    #[test]
+    fn curved_segment_costs_its_spline_length() {
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let curve = network.add_segment(a, b, vec![Vec3::new(50.0, 0.0, 40.0)], RoadType::Dirt, 2.0);
+
+        let length = network.segment_length(curve).unwrap();
+        assert!(length > 120.0);
+        let path = network.find_path(a, b).unwrap();
+        assert!((path.length - length).abs() < 0.01);
+    }
+
+    #[test]
+    fn unreachable_node_has_no_path() {
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(10.0, 0.0, 0.0));
+        let island = network.add_node(Vec3::new(50.0, 0.0, 0.0));
+        road(&mut network, a, b, RoadType::Dirt);
+
+        assert!(network.find_path(a, island).is_none());
+        assert!(network.travel_times_from(&[(a, 0.0)], f32::INFINITY).time_to(island).is_none());
+    }
+
+    #[test]
+    fn one_to_many_respects_time_limit() {
+        // a -- b -- c -- d, 40 units apart on dirt: 5 s per hop.
+        let mut network = RoadNetwork::default();
+        let nodes: Vec<NodeId> = (0..4)
+            .map(|i| network.add_node(Vec3::new(i as f32 * 40.0, 0.0, 0.0)))
+            .collect();
+        for pair in nodes.windows(2) {
+            road(&mut network, pair[0], pair[1], RoadType::Dirt);
+        }
+
+        let times = network.travel_times_from(&[(nodes[0], 0.0)], 12.0);
+        assert_eq!(times.time_to(nodes[0]), Some(0.0));
+        assert!((times.time_to(nodes[1]).unwrap() - 5.0).abs() < 0.01);
+        assert!((times.time_to(nodes[2]).unwrap() - 10.0).abs() < 0.01);
+        assert!(times.time_to(nodes[3]).is_none());
+        assert_eq!(times.iter().count(), 3);
+        assert_eq!(times.path_to(nodes[2]).unwrap().nodes, nodes[..3].to_vec());
+    }
+
+    #[test]
+    fn one_to_many_from_several_sources() {
+        // Seeding both ends of the middle segment, as if starting halfway
+        // along it, reaches each outer node from the nearer end.
+        let mut network = RoadNetwork::default();
+        let nodes: Vec<NodeId> = (0..4)
+            .map(|i| network.add_node(Vec3::new(i as f32 * 40.0, 0.0, 0.0)))
+            .collect();
+        for pair in nodes.windows(2) {
+            road(&mut network, pair[0], pair[1], RoadType::Dirt);
+        }
+
+        let times = network.travel_times_from(&[(nodes[1], 2.5), (nodes[2], 2.5)], f32::INFINITY);
+        assert!((times.time_to(nodes[0]).unwrap() - 7.5).abs() < 0.01);
+        assert!((times.time_to(nodes[3]).unwrap() - 7.5).abs() < 0.01);
+        assert_eq!(times.path_to(nodes[3]).unwrap().nodes, vec![nodes[2], nodes[3]]);
+    }
+
+    #[test]
+    fn point_isochrone_follows_the_road() {
+        // Two 40-unit dirt roads meeting at b. d is close to a as the crow
+        // flies but not connected to anything.
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(40.0, 0.0, 0.0));
+        let c = network.add_node(Vec3::new(40.0, 0.0, 40.0));
+        let d = network.add_node(Vec3::new(0.0, 0.0, 10.0));
+        let ab = road(&mut network, a, b, RoadType::Dirt);
+        let bc = road(&mut network, b, c, RoadType::Dirt);
+
+        // From a quarter of the way along ab (10 units from a)
+        let times = network.travel_times_from_point(ab, 0.25, 20.0);
+        let same_segment = times.time_to_point(&network, ab, 0.75).unwrap();
+        assert!((same_segment - 20.0 / DIRT_ROAD_SPEED).abs() < 0.01);
+        let around_corner = times.time_to_point(&network, bc, 0.5).unwrap();
+        assert!((around_corner - 50.0 / DIRT_ROAD_SPEED).abs() < 0.01);
+        assert!(times.time_to(d).is_none());
+
+        // A tight limit cuts off the far end of the corner road
+        let near = network.travel_times_from_point(ab, 0.25, 6.0);
+        assert!(near.time_to_point(&network, bc, 0.25).is_some());
+        assert!(near.time_to_point(&network, bc, 0.75).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Tunable parameters for heightmap noise, terrain shaping and biome
+/// classification. Lives on `TerrainConfig` and can be loaded from a RON file
+/// with `--profile <path>`, or picked from the built-in presets by name.
+///
+/// Fields missing from a file keep their `Default` value, so a profile only
+/// needs to list what it changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationProfile {
+    pub name: String,
+
+    // --- Heightmap noise ---
+    /// fBm octaves. More octaves add finer detail.
+    pub octaves: usize,
+    /// Noise cycles across the map width (~number of hills side to side).
+    pub frequency: f64,
+    /// Amplitude falloff per octave. Higher is rougher.
+    pub persistence: f64,
+
+    // --- Shaping (applied to normalized 0.0–1.0 heights) ---
+    /// Added to every height before shaping. Negative drowns more of the map.
+    pub elevation_bias: f32,
+    /// Heights are raised to this power. Above 1.0 flattens lowlands and
+    /// sharpens peaks.
+    pub elevation_exponent: f32,
+    /// Extra height at the map edges, fading to zero at the center. Positive
+    /// rings the map with hills (a basin), negative sinks the edges.
+    pub edge_lift: f32,
+    /// Height lost from the north edge to the south edge, tilting the map
+    /// down toward a southern coast.
+    pub tilt: f32,
+
+    // --- Biome thresholds ---
+    /// World units above sea level that are still beach sand.
+    pub shore_margin: f32,
+    /// Surface normal Y below which ground is Rock (cliffs).
+    pub rock_flatness: f32,
+    /// Normalized elevation above which ground is Rock.
+    pub rock_elevation: f32,
+    /// Surface normal Y below which ground counts as sloped.
+    pub slope_flatness: f32,
+    /// Normalized elevation above which ground counts as upland.
+    pub upland_elevation: f32,
+
+    // --- Climate biome thresholds (moisture and temperature are 0.0–1.0) ---
+    /// Surface normal Y above which wet lowland can be Marsh.
+    pub marsh_flatness: f32,
+    /// Moisture above which flat lowland is Marsh.
+    pub marsh_moisture: f32,
+    /// World units above sea level that can still be Marsh.
+    pub marsh_margin: f32,
+    /// Temperature below which ground is tundra-like Grass or Dirt.
+    pub cold_temperature: f32,
+    /// Moisture below which ground is Scrub at any temperature.
+    pub scrub_moisture: f32,
+    /// Temperature above which drier ground turns to Scrub.
+    pub hot_temperature: f32,
+    /// Moisture below which hot ground is Scrub.
+    pub hot_scrub_moisture: f32,
+    /// Moisture above which sloped ground is Forest rather than Dirt.
+    pub slope_forest_moisture: f32,
+    /// Moisture above which flat ground is Meadow.
+    pub meadow_moisture: f32,
+    /// Moisture above which flat ground is Forest.
+    pub forest_moisture: f32,
+}
+
+impl Default for GenerationProfile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            octaves: 6,
+            frequency: 2.0,
+            persistence: 0.5,
+            elevation_bias: 0.0,
+            elevation_exponent: 1.0,
+            edge_lift: 0.0,
+            tilt: 0.0,
+            shore_margin: 2.0,
+            rock_flatness: 0.85,
+            rock_elevation: 0.7,
+            slope_flatness: 0.93,
+            upland_elevation: 0.5,
+            marsh_flatness: 0.97,
+            marsh_moisture: 0.75,
+            marsh_margin: 3.0,
+            cold_temperature: 0.25,
+            scrub_moisture: 0.2,
+            hot_temperature: 0.75,
+            hot_scrub_moisture: 0.35,
+            slope_forest_moisture: 0.35,
+            meadow_moisture: 0.6,
+            forest_moisture: 0.4,
+        }
+    }
+}
+
+impl GenerationProfile {
+    /// Broad central lowland between gentle hills, good for long rivers.
+    pub fn river_valley() -> Self {
+        Self {
+            name: "River Valley".to_string(),
+            frequency: 1.5,
+            elevation_bias: 0.05,
+            edge_lift: 0.25,
+            ..Self::default()
+        }
+    }
+
+    /// Land sloping down to open sea along the south edge.
+    pub fn coastal() -> Self {
+        Self {
+            name: "Coastal".to_string(),
+            elevation_bias: 0.2,
+            tilt: 0.45,
+            shore_margin: 3.0,
+            ..Self::default()
+        }
+    }
+
+    /// Rugged ring of mountains around an enclosed lowland.
+    pub fn mountain_basin() -> Self {
+        Self {
+            name: "Mountain Basin".to_string(),
+            octaves: 7,
+            persistence: 0.55,
+            elevation_bias: -0.05,
+            elevation_exponent: 1.3,
+            edge_lift: 0.5,
+            ..Self::default()
+        }
+    }
+
+    /// Wide, mostly flat farmland with low rolling hills.
+    pub fn plains() -> Self {
+        Self {
+            name: "Plains".to_string(),
+            octaves: 4,
+            frequency: 1.5,
+            persistence: 0.35,
+            elevation_bias: 0.1,
+            elevation_exponent: 1.6,
+            ..Self::default()
+        }
+    }
+
+    /// All built-in profiles, default first.
+    pub fn presets() -> Vec<GenerationProfile> {
+        vec![
+            Self::default(),
+            Self::river_valley(),
+            Self::coastal(),
+            Self::mountain_basin(),
+            Self::plains(),
+        ]
+    }
+
+    /// Look up a preset by name, ignoring case, spaces, dashes and underscores
+    /// (so `mountain-basin` matches "Mountain Basin").
+    pub fn preset(name: &str) -> Option<GenerationProfile> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let wanted = normalize(name);
+        Self::presets().into_iter().find(|p| normalize(&p.name) == wanted)
+    }
+
+    /// Load a profile from a RON file.
+    pub fn load(path: &Path) -> io::Result<GenerationProfile> {
+        let text = fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// Write the profile as pretty-printed RON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("profile_{}_{name}", std::process::id()))
+    }
+
+    #[test]
+    fn preset_names_match_loosely() {
+        assert_eq!(GenerationProfile::preset("mountain-basin"), Some(GenerationProfile::mountain_basin()));
+        assert_eq!(GenerationProfile::preset("River_Valley"), Some(GenerationProfile::river_valley()));
+        assert_eq!(GenerationProfile::preset("volcano"), None);
+    }
+
+    #[test]
+    fn partial_file_keeps_defaults() {
+        let path = temp_path("partial.ron");
+        fs::write(&path, "(name: \"Steep\", octaves: 8, marsh_moisture: 0.9)").unwrap();
+        let loaded = GenerationProfile::load(&path);
+        let _ = fs::remove_file(&path);
+
+        let expected = GenerationProfile {
+            name: "Steep".to_string(),
+            octaves: 8,
+            marsh_moisture: 0.9,
+            ..GenerationProfile::default()
+        };
+        assert_eq!(loaded.unwrap(), expected);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("coastal.ron");
+        let profile = GenerationProfile {
+            cold_temperature: 0.1,
+            ..GenerationProfile::coastal()
+        };
+        profile.save(&path).unwrap();
+        let loaded = GenerationProfile::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), profile);
+    }
+
+    #[test]
+    fn malformed_file_is_invalid_data() {
+        let path = temp_path("broken.ron");
+        fs::write(&path, "(octaves: \"many\")").unwrap();
+        let loaded = GenerationProfile::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
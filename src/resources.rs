@@ -1,7 +1,26 @@
+#[cfg(feature = "gui")]
+use bevy::asset::RenderAssetUsages;
+#[cfg(feature = "gui")]
+use bevy::light::NotShadowCaster;
+#[cfg(feature = "gui")]
+use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::prelude::*;
+#[cfg(feature = "gui")]
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use std::collections::HashSet;
 
-use crate::terrain::{Biome, BiomeMap, Heightmap, TerrainConfig};
+use crate::climate::ClimateMap;
+use crate::erosion::SedimentMap;
+use crate::terrain::{Biome, BiomeMap, CellRect, Heightmap, TerrainConfig};
+#[cfg(feature = "gui")]
+use crate::terrain::{lod_axis, TerrainChunk};
+use crate::water::{flood_component, RiverNetwork, WaterBodies};
+
+/// Richness a cell needs before a producer will set up to extract from it.
+/// Producers close once no cell in their extraction radius is above it.
+#[cfg(feature = "gui")]
+pub const VIABLE_RICHNESS: f32 = 0.2;
 
 /// Map resource types that can be harvested by industries.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -14,6 +33,14 @@ pub enum ResourceType {
 }
 
 impl ResourceType {
+    pub const ALL: [ResourceType; 5] = [
+        ResourceType::Timber,
+        ResourceType::FertileLand,
+        ResourceType::Coal,
+        ResourceType::Clay,
+        ResourceType::Stone,
+    ];
+
     /// Color tint applied to terrain where this resource is present.
     pub fn color(&self) -> [f32; 4] {
         match self {
@@ -25,6 +52,17 @@ impl ResourceType {
         }
     }
 
+    /// Richness regained per game second, up to the cell's capacity.
+    /// Timber regrows and fallow farmland recovers; mineral deposits are finite.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn regrowth_rate(&self) -> f32 {
+        match self {
+            ResourceType::Timber => 0.001,
+            ResourceType::FertileLand => 0.002,
+            ResourceType::Coal | ResourceType::Clay | ResourceType::Stone => 0.0,
+        }
+    }
+
     pub fn label(&self) -> &str {
         match self {
             ResourceType::Timber => "Timber",
@@ -34,43 +72,267 @@ impl ResourceType {
             ResourceType::Stone => "Stone",
         }
     }
+
+    /// Surface resources are visible from the start. Subsurface ones (Coal,
+    /// Clay, Stone) stay hidden until their cell is surveyed.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn is_surface(&self) -> bool {
+        matches!(self, ResourceType::Timber | ResourceType::FertileLand)
+    }
+
+    /// Name for one connected deposit of this resource, e.g. "Coal seam".
+    #[cfg(feature = "gui")]
+    pub fn deposit_label(&self) -> &str {
+        match self {
+            ResourceType::Timber => "Timber stand",
+            ResourceType::FertileLand => "Fertile field",
+            ResourceType::Coal => "Coal seam",
+            ResourceType::Clay => "Clay bed",
+            ResourceType::Stone => "Stone outcrop",
+        }
+    }
 }
 
-/// Per-cell resource data.
+/// One resource present in a cell.
 #[derive(Clone, Copy)]
-pub struct ResourceCell {
+pub struct ResourceEntry {
     pub resource: ResourceType,
     /// Richness from 0.0 to 1.0 — how much of this resource is here.
+    /// Drops as producers extract it.
     pub richness: f32,
+    /// Richness at generation. Renewable resources regrow toward it.
+    pub capacity: f32,
+}
+
+/// Per-cell resource data: every resource present in the cell, at most one
+/// entry per type. Most cells hold zero or one entry; coal under a forest
+/// holds two.
+#[derive(Clone, Default)]
+pub struct ResourceCell {
+    pub entries: Vec<ResourceEntry>,
+}
+
+impl ResourceCell {
+    pub fn get(&self, resource: ResourceType) -> Option<&ResourceEntry> {
+        self.entries.iter().find(|entry| entry.resource == resource)
+    }
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn get_mut(&mut self, resource: ResourceType) -> Option<&mut ResourceEntry> {
+        self.entries.iter_mut().find(|entry| entry.resource == resource)
+    }
+}
+
+/// Color for a set of resource entries: their colors mixed by richness, with
+/// the highest richness as alpha. `None` if there are no entries.
+pub fn resource_tint<'a>(entries: impl Iterator<Item = &'a ResourceEntry>) -> Option<[f32; 4]> {
+    let mut rgb = [0.0; 3];
+    let mut weight = 0.0;
+    let mut alpha: Option<f32> = None;
+    for entry in entries {
+        let color = entry.resource.color();
+        // Keep depleted entries from vanishing from the mix entirely
+        let w = entry.richness.max(0.01);
+        for channel in 0..3 {
+            rgb[channel] += color[channel] * w;
+        }
+        weight += w;
+        alpha = Some(alpha.map_or(entry.richness, |a: f32| a.max(entry.richness)));
+    }
+    let alpha = alpha?;
+    Some([rgb[0] / weight, rgb[1] / weight, rgb[2] / weight, alpha])
+}
+
+/// Entries of a cell the player can see: surface resources, plus subsurface
+/// ones if the cell is surveyed.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn visible_entries(cell: &ResourceCell, surveyed: bool) -> impl Iterator<Item = &ResourceEntry> {
+    cell.entries
+        .iter()
+        .filter(move |entry| surveyed || entry.resource.is_surface())
 }
 
 /// Spatial grid of resources overlaid on the terrain, same resolution as heightmap.
+///
+/// `surveyed` marks cells the player has prospected (with the survey tool or
+/// an extractor working nearby). Gameplay and UI go through [`ResourceMap::known`]
+/// so subsurface resources stay hidden until surveyed.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 #[derive(Resource)]
 pub struct ResourceMap {
-    pub cells: Vec<Option<ResourceCell>>,
+    pub cells: Vec<ResourceCell>,
+    pub surveyed: Vec<bool>,
     pub resolution: u32,
+    /// Cells holding a renewable resource below capacity, the only ones
+    /// `regrow` has to visit.
+    regrowing: HashSet<usize>,
+    /// Cells whose hidden resources were revealed since the terrain tint was
+    /// last rebuilt.
+    revealed: Option<CellRect>,
 }
 
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 impl ResourceMap {
-    pub fn get(&self, row: u32, col: u32) -> Option<ResourceCell> {
+    /// A map of `resolution`² cells (row-major), none of them surveyed.
+    pub fn new(cells: Vec<ResourceCell>, resolution: u32) -> Self {
+        Self {
+            surveyed: vec![false; cells.len()],
+            cells,
+            resolution,
+            regrowing: HashSet::new(),
+            revealed: None,
+        }
+    }
+
+    /// Remove up to `amount` of a resource from a cell, queueing renewable
+    /// resources to regrow. Returns the richness left, or `None` if the cell
+    /// holds none of the resource.
+    pub fn extract(&mut self, row: u32, col: u32, resource: ResourceType, amount: f32) -> Option<f32> {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        let i = (row * self.resolution + col) as usize;
+        let entry = self.cells[i].get_mut(resource)?;
+        entry.richness = (entry.richness - amount).max(0.0);
+        if resource.regrowth_rate() > 0.0 && entry.richness < entry.capacity {
+            self.regrowing.insert(i);
+        }
+        Some(entry.richness)
+    }
+
+    /// Whether any renewable resource is below capacity, i.e. `regrow` would
+    /// change anything.
+    pub fn is_regrowing(&self) -> bool {
+        !self.regrowing.is_empty()
+    }
+
+    /// Regrow renewable resources by `dt` game seconds of
+    /// `ResourceType::regrowth_rate`, up to their capacity.
+    pub fn regrow(&mut self, dt: f32) {
+        let cells = &mut self.cells;
+        self.regrowing.retain(|&i| {
+            let mut below_capacity = false;
+            for entry in &mut cells[i].entries {
+                let regrowth = entry.resource.regrowth_rate();
+                if regrowth > 0.0 && entry.richness < entry.capacity {
+                    entry.richness = (entry.richness + regrowth * dt).min(entry.capacity);
+                    below_capacity |= entry.richness < entry.capacity;
+                }
+            }
+            below_capacity
+        });
+    }
+
+    pub fn get(&self, row: u32, col: u32) -> &ResourceCell {
         let row = row.min(self.resolution - 1);
         let col = col.min(self.resolution - 1);
-        self.cells[(row * self.resolution + col) as usize]
+        &self.cells[(row * self.resolution + col) as usize]
+    }
+
+    /// Sample the resources at a world-space (x, z) position.
+    pub fn sample_world(&self, x: f32, z: f32, map_size: f32) -> &ResourceCell {
+        let (row, col) = self.world_to_cell(x, z, map_size);
+        self.get(row, col)
+    }
+
+    /// The resources at a cell as far as the player knows: surface resources
+    /// always, subsurface ones only once the cell is surveyed.
+    pub fn known(&self, row: u32, col: u32) -> impl Iterator<Item = &ResourceEntry> {
+        visible_entries(self.get(row, col), self.is_surveyed(row, col))
+    }
+
+    /// Known resources at a world-space (x, z) position.
+    pub fn known_world(&self, x: f32, z: f32, map_size: f32) -> impl Iterator<Item = &ResourceEntry> {
+        let (row, col) = self.world_to_cell(x, z, map_size);
+        visible_entries(self.sample_world(x, z, map_size), self.is_surveyed(row, col))
     }
 
-    /// Sample the resource at a world-space (x, z) position.
-    pub fn sample_world(&self, x: f32, z: f32, map_size: f32) -> Option<ResourceCell> {
+    pub fn is_surveyed(&self, row: u32, col: u32) -> bool {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.surveyed[(row * self.resolution + col) as usize]
+    }
+
+    /// Grid cell containing a world-space (x, z) position, clamped to the map.
+    pub fn world_to_cell(&self, x: f32, z: f32, map_size: f32) -> (u32, u32) {
         let half = map_size / 2.0;
         let cell_size = map_size / self.resolution as f32;
         let col = ((x + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
         let row = ((z + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
-        self.get(row, col)
+        (row, col)
+    }
+
+    /// (row, col) of every cell whose center lies within `radius` of the
+    /// world-space `center`.
+    pub fn cells_in_radius(&self, center: Vec2, radius: f32, map_size: f32) -> Vec<(u32, u32)> {
+        let half = map_size / 2.0;
+        let cell_size = map_size / self.resolution as f32;
+        let (min_row, min_col) = self.world_to_cell(center.x - radius, center.y - radius, map_size);
+        let (max_row, max_col) = self.world_to_cell(center.x + radius, center.y + radius, map_size);
+
+        let mut cells = Vec::new();
+        for row in min_row..=max_row {
+            for col in min_col..=max_col {
+                let cell_center = Vec2::new(
+                    (col as f32 + 0.5) * cell_size - half,
+                    (row as f32 + 0.5) * cell_size - half,
+                );
+                if cell_center.distance(center) <= radius {
+                    cells.push((row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Mark every cell within `radius` of `center` as surveyed. Returns the
+    /// bounds of the cells where a hidden resource was revealed, if any, and
+    /// queues them for `take_revealed`.
+    pub fn survey(&mut self, center: Vec2, radius: f32, map_size: f32) -> Option<CellRect> {
+        let cells = self.cells_in_radius(center, radius, map_size);
+        self.survey_cells(cells)
+    }
+
+    /// Mark the given (row, col) cells as surveyed, like `survey`.
+    pub fn survey_cells(&mut self, cells: impl IntoIterator<Item = (u32, u32)>) -> Option<CellRect> {
+        let mut revealed: Option<CellRect> = None;
+        for (row, col) in cells {
+            let i = (row * self.resolution + col) as usize;
+            if self.surveyed[i] {
+                continue;
+            }
+            self.surveyed[i] = true;
+            if self.cells[i].entries.iter().any(|entry| !entry.resource.is_surface()) {
+                let cell = CellRect::cell(row, col);
+                revealed = Some(revealed.map_or(cell, |rect| rect.union(cell)));
+            }
+        }
+        if let Some(rect) = revealed {
+            self.revealed = Some(self.revealed.map_or(rect, |pending| pending.union(rect)));
+        }
+        revealed
+    }
+
+    /// Whether any surveyed cells are waiting for `take_revealed`.
+    pub fn has_revealed(&self) -> bool {
+        self.revealed.is_some()
+    }
+
+    /// Bounds of every cell revealed by surveys since the last call, so the
+    /// terrain tint under them can be rebuilt.
+    pub fn take_revealed(&mut self) -> Option<CellRect> {
+        self.revealed.take()
     }
 }
 
-/// Check if any cell within `radius` cells is below water level.
-fn near_water(heightmap: &Heightmap, row: u32, col: u32, radius: u32, water_level: f32) -> bool {
-    let res = heightmap.resolution;
+/// Check if any cell within `radius` cells is in a water body or a river.
+fn near_water(
+    water_bodies: &WaterBodies,
+    rivers: &RiverNetwork,
+    row: u32,
+    col: u32,
+    radius: u32,
+    res: u32,
+) -> bool {
     let r_start = row.saturating_sub(radius);
     let r_end = (row + radius + 1).min(res);
     let c_start = col.saturating_sub(radius);
@@ -78,7 +340,7 @@ fn near_water(heightmap: &Heightmap, row: u32, col: u32, radius: u32, water_leve
 
     for r in r_start..r_end {
         for c in c_start..c_end {
-            if heightmap.get(r, c) < water_level {
+            if water_bodies.is_water(r, c) || rivers.is_river(r, c) {
                 return true;
             }
         }
@@ -86,56 +348,52 @@ fn near_water(heightmap: &Heightmap, row: u32, col: u32, radius: u32, water_leve
     false
 }
 
-/// Compute terrain flatness at a grid cell (same approach as biome generation).
-fn cell_flatness(heightmap: &Heightmap, row: u32, col: u32, cell_size: f32) -> f32 {
-    let res = heightmap.resolution;
-    let height = heightmap.get(row, col);
-    let h_left = if col > 0 { heightmap.get(row, col - 1) } else { height };
-    let h_right = if col < res - 1 { heightmap.get(row, col + 1) } else { height };
-    let h_down = if row > 0 { heightmap.get(row - 1, col) } else { height };
-    let h_up = if row < res - 1 { heightmap.get(row + 1, col) } else { height };
-
-    Vec3::new(h_left - h_right, 2.0 * cell_size, h_down - h_up)
-        .normalize()
-        .y
-}
-
 /// Generate resource deposits based on terrain features.
 ///
 /// Runs once at startup after heightmap and biome map generation.
-/// Each cell gets at most one resource type, prioritized by specificity:
-/// Coal > Clay > Stone > Fertile Land > Timber.
+/// Each resource type is placed independently, so a cell can hold several
+/// (e.g. Coal under Timber), each with its own richness.
+///
+/// Sediment left by the erosion pass favors Clay and Fertile Land, so both
+/// gather on valley floors and alluvial fans rather than pure noise blobs.
+/// Timber grows in Forest and Fertile Land lies in Meadow, each richer where
+/// the ground is moister.
+#[allow(clippy::too_many_arguments)]
 pub fn generate_resource_map(
     mut commands: Commands,
     config: Res<TerrainConfig>,
     heightmap: Res<Heightmap>,
     biome_map: Res<BiomeMap>,
+    climate: Res<ClimateMap>,
+    sediment_map: Res<SedimentMap>,
+    rivers: Res<RiverNetwork>,
+    water_bodies: Res<WaterBodies>,
 ) {
     let res = heightmap.resolution;
     let cell_size = config.map_size / res as f32;
 
     // Separate noise generators per resource for uncorrelated clustering
-    let timber_noise = Fbm::<Perlin>::new(100)
+    let timber_noise = Fbm::<Perlin>::new(config.derive_seed(100))
         .set_octaves(4)
         .set_frequency(4.0 / config.map_size as f64)
         .set_persistence(0.5);
 
-    let coal_noise = Fbm::<Perlin>::new(200)
+    let coal_noise = Fbm::<Perlin>::new(config.derive_seed(200))
         .set_octaves(3)
         .set_frequency(8.0 / config.map_size as f64)
         .set_persistence(0.6);
 
-    let clay_noise = Fbm::<Perlin>::new(300)
+    let clay_noise = Fbm::<Perlin>::new(config.derive_seed(300))
         .set_octaves(3)
         .set_frequency(6.0 / config.map_size as f64)
         .set_persistence(0.5);
 
-    let stone_noise = Fbm::<Perlin>::new(400)
+    let stone_noise = Fbm::<Perlin>::new(config.derive_seed(400))
         .set_octaves(3)
         .set_frequency(6.0 / config.map_size as f64)
         .set_persistence(0.5);
 
-    let fertility_noise = Fbm::<Perlin>::new(500)
+    let fertility_noise = Fbm::<Perlin>::new(config.derive_seed(500))
         .set_octaves(3)
         .set_frequency(3.0 / config.map_size as f64)
         .set_persistence(0.5);
@@ -147,117 +405,546 @@ pub fn generate_resource_map(
             let height = heightmap.get(row, col);
             let biome = biome_map.get(row, col);
             let elevation_t = height / config.height_scale;
-            let flatness = cell_flatness(&heightmap, row, col, cell_size);
 
             let x = col as f64 * cell_size as f64;
             let z = row as f64 * cell_size as f64;
 
-            let is_underwater = height < config.water_level;
-            let is_near_water = near_water(&heightmap, row, col, 5, config.water_level);
+            // 0.0 = bare or eroded ground, 1.0 = thick alluvial deposit
+            let sediment_t = (sediment_map.get(row, col) / 0.5).clamp(0.0, 1.0) as f64;
+
+            let moisture = climate.moisture(row, col);
+            let is_underwater = water_bodies.is_water(row, col) || rivers.is_river(row, col);
+            let is_near_water = near_water(&water_bodies, &rivers, row, col, 5, res);
 
             // Pre-sample all noise values
             let cn = coal_noise.get([x, z]);
-            let cln = clay_noise.get([x, z]);
+            let cln = clay_noise.get([x, z]) + sediment_t * 0.5;
             let sn = stone_noise.get([x, z]);
-            let fn_val = fertility_noise.get([x, z]);
+            let fn_val = fertility_noise.get([x, z]) + sediment_t * 0.6;
             let tn = timber_noise.get([x, z]);
 
-            let cell = if is_underwater {
-                None
-            }
-            // Coal: clustered deposits in hilly/rocky terrain
-            else if (biome == Biome::Dirt || biome == Biome::Rock)
-                && elevation_t > 0.4
-                && cn > 0.5
-            {
-                let richness = ((cn - 0.5) / 0.5).clamp(0.0, 1.0) as f32;
-                Some(ResourceCell { resource: ResourceType::Coal, richness })
-            }
-            // Clay: near water, low elevation
-            else if is_near_water
-                && height > config.water_level
-                && height < config.water_level + 4.0
-                && cln > 0.3
-            {
-                let richness = ((cln - 0.3) / 0.7).clamp(0.0, 1.0) as f32;
-                Some(ResourceCell { resource: ResourceType::Clay, richness })
-            }
-            // Stone: rocky, steep terrain
-            else if biome == Biome::Rock && sn > 0.3 {
-                let richness = ((sn - 0.3) / 0.7).clamp(0.0, 1.0) as f32;
-                Some(ResourceCell { resource: ResourceType::Stone, richness })
-            }
-            // Fertile Land: very flat, low, near water
-            else if flatness > 0.96
-                && is_near_water
-                && height > config.water_level
-                && height < config.water_level + 6.0
-                && fn_val > -0.2
-            {
-                let richness = ((fn_val + 0.2) / 1.2).clamp(0.3, 1.0) as f32;
-                Some(ResourceCell { resource: ResourceType::FertileLand, richness })
-            }
-            // Timber: mid-elevation grassland/dirt, moderate slope
-            else if (biome == Biome::Grass || biome == Biome::Dirt)
-                && flatness > 0.88
-                && height > config.water_level + 3.0
-                && tn > 0.2
-            {
-                let richness = ((tn - 0.2) / 0.8).clamp(0.0, 1.0) as f32;
-                Some(ResourceCell { resource: ResourceType::Timber, richness })
-            }
-            else {
-                None
+            // Each resource is placed independently, so coal can lie under a
+            // forest and clay under a meadow
+            let mut cell = ResourceCell::default();
+            let mut place = |resource: ResourceType, richness: f32| {
+                cell.entries.push(ResourceEntry { resource, richness, capacity: richness });
             };
 
+            if !is_underwater {
+                // Coal: clustered deposits in hilly/rocky terrain
+                if matches!(biome, Biome::Dirt | Biome::Rock | Biome::Forest | Biome::Scrub)
+                    && elevation_t > 0.4
+                    && cn > 0.5
+                {
+                    place(ResourceType::Coal, ((cn - 0.5) / 0.5).clamp(0.0, 1.0) as f32);
+                }
+                // Clay: near water, low elevation
+                if is_near_water
+                    && height > config.water_level
+                    && height < config.water_level + 4.0
+                    && cln > 0.3
+                {
+                    place(ResourceType::Clay, ((cln - 0.3) / 0.7).clamp(0.0, 1.0) as f32);
+                }
+                // Stone: rocky, steep terrain
+                if biome == Biome::Rock && sn > 0.3 {
+                    place(ResourceType::Stone, ((sn - 0.3) / 0.7).clamp(0.0, 1.0) as f32);
+                }
+                // Fertile Land: moist meadows, richest on alluvial soil
+                if biome == Biome::Meadow && fn_val > -0.2 {
+                    let soil = ((fn_val + 0.2) / 1.2).clamp(0.0, 1.0) as f32;
+                    place(ResourceType::FertileLand, (soil * 0.5 + moisture * 0.5).clamp(0.3, 1.0));
+                }
+                // Timber: forests, densest where moist
+                if biome == Biome::Forest && tn > -0.3 {
+                    let density = ((tn + 0.3) / 1.3).clamp(0.0, 1.0) as f32;
+                    place(ResourceType::Timber, (density * 0.6 + moisture * 0.4).clamp(0.2, 1.0));
+                }
+            }
+
             cells.push(cell);
         }
     }
 
-    commands.insert_resource(ResourceMap { cells, resolution: res });
+    commands.insert_resource(ResourceMap::new(cells, res));
 }
 
 // ---------------------------------------------------------------------------
-// Resource debug overlay (F4)
+// Resource deposits
 // ---------------------------------------------------------------------------
 
-pub fn draw_resource_debug(
-    visible: Res<crate::ui::DebugVisible>,
-    resource_map: Res<ResourceMap>,
-    heightmap: Res<Heightmap>,
-    config: Res<TerrainConfig>,
-    mut gizmos: Gizmos,
-) {
-    if !visible.0 {
-        return;
+/// Unique identifier for a resource deposit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DepositId(pub u32);
+
+/// A connected region of cells holding the same resource, e.g. one coal seam.
+pub struct ResourceDeposit {
+    pub id: DepositId,
+    pub resource: ResourceType,
+    /// Grid cell indices (row * resolution + col) in the deposit.
+    pub cells: Vec<usize>,
+    /// Summed richness of all cells at generation.
+    pub total_reserves: f32,
+    /// Richness-weighted center in world (x, z).
+    pub centroid: Vec2,
+    pub bounds: CellRect,
+}
+
+impl ResourceDeposit {
+    /// Summed richness of all cells now, after extraction and regrowth.
+    #[cfg(feature = "gui")]
+    pub fn remaining(&self, resource_map: &ResourceMap) -> f32 {
+        self.cells
+            .iter()
+            .filter_map(|&i| resource_map.cells[i].get(self.resource))
+            .map(|entry| entry.richness)
+            .sum()
     }
 
-    let res = resource_map.resolution;
+    /// Remaining reserves as a fraction (0.0–1.0) of `total_reserves`.
+    #[cfg(feature = "gui")]
+    pub fn remaining_fraction(&self, resource_map: &ResourceMap) -> f32 {
+        if self.total_reserves <= 0.0 {
+            return 0.0;
+        }
+        (self.remaining(resource_map) / self.total_reserves).clamp(0.0, 1.0)
+    }
+
+    /// Display name, e.g. "Coal seam #3".
+    #[cfg(feature = "gui")]
+    pub fn label(&self) -> String {
+        format!("{} #{}", self.resource.deposit_label(), self.id.0)
+    }
+}
+
+/// All resource deposits on the map, with a per-cell lookup of which deposit
+/// (if any) of each resource type each grid cell belongs to.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Resource)]
+pub struct ResourceDeposits {
+    pub deposits: Vec<ResourceDeposit>,
+    /// Indexed by cell, then by `ResourceType as usize`.
+    cell_deposits: Vec<[Option<DepositId>; ResourceType::ALL.len()]>,
+    resolution: u32,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl ResourceDeposits {
+    pub fn deposit(&self, id: DepositId) -> Option<&ResourceDeposit> {
+        self.deposits.get(id.0 as usize)
+    }
+
+    /// The deposit of `resource` containing this grid cell, if any.
+    pub fn deposit_at(&self, row: u32, col: u32, resource: ResourceType) -> Option<&ResourceDeposit> {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.cell_deposits[(row * self.resolution + col) as usize][resource as usize]
+            .and_then(|id| self.deposit(id))
+    }
+
+    /// Grid cell containing a world-space (x, z) position, clamped to the map.
+    fn world_to_cell(&self, x: f32, z: f32, map_size: f32) -> (u32, u32) {
+        let half = map_size / 2.0;
+        let cell_size = map_size / self.resolution as f32;
+        let col = ((x + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        let row = ((z + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        (row, col)
+    }
+
+    /// The deposit of `resource` containing the world-space (x, z) position,
+    /// if any. Includes deposits the player has not surveyed yet.
+    pub fn deposit_at_world(
+        &self,
+        x: f32,
+        z: f32,
+        map_size: f32,
+        resource: ResourceType,
+    ) -> Option<&ResourceDeposit> {
+        let (row, col) = self.world_to_cell(x, z, map_size);
+        self.deposit_at(row, col, resource)
+    }
+}
+
+/// Group resource cells into deposits: 4-connected regions holding the same
+/// resource type. Deposits of different types may overlap. Runs after
+/// `generate_resource_map`.
+pub fn generate_resource_deposits(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    resource_map: Res<ResourceMap>,
+) {
+    let res = resource_map.resolution as usize;
+    let count = res * res;
     let cell_size = config.map_size / res as f32;
     let half = config.map_size / 2.0;
-    let rect_size = Vec2::splat(cell_size * 0.9);
-    // Rotate from default XY plane to horizontal XZ plane
-    let flat_rot = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
-    let yellow = Color::srgb(1.0, 1.0, 0.0);
 
-    for row in 0..res {
-        for col in 0..res {
-            let Some(cell) = resource_map.get(row, col) else {
+    let mut deposits = Vec::new();
+    let mut cell_deposits = vec![[None; ResourceType::ALL.len()]; count];
+
+    for resource in ResourceType::ALL {
+        let has_resource = |i: usize| resource_map.cells[i].get(resource).is_some();
+        let mut assigned = vec![false; count];
+
+        for start in 0..count {
+            if assigned[start] || !has_resource(start) {
                 continue;
+            }
+            let cells = flood_component(start, res, has_resource, &mut assigned);
+
+            let id = DepositId(deposits.len() as u32);
+            let mut total_reserves = 0.0;
+            let mut weighted = Vec2::ZERO;
+            let mut bounds = CellRect {
+                min_row: u32::MAX,
+                min_col: u32::MAX,
+                max_row: 0,
+                max_col: 0,
+            };
+            for &i in &cells {
+                cell_deposits[i][resource as usize] = Some(id);
+                let (row, col) = ((i / res) as u32, (i % res) as u32);
+                let richness = resource_map.cells[i].get(resource).map_or(0.0, |entry| entry.capacity);
+                total_reserves += richness;
+                weighted += Vec2::new(col as f32 + 0.5, row as f32 + 0.5) * richness;
+                bounds = bounds.union(CellRect::cell(row, col));
+            }
+            let centroid = if total_reserves > 0.0 {
+                weighted / total_reserves * cell_size - Vec2::splat(half)
+            } else {
+                bounds.world_bounds(res as u32, config.map_size).0
             };
-            if cell.richness <= 0.2 {
-                continue; // below viability threshold
+
+            deposits.push(ResourceDeposit {
+                id,
+                resource,
+                cells,
+                total_reserves,
+                centroid,
+                bounds,
+            });
+        }
+    }
+
+    commands.insert_resource(ResourceDeposits {
+        deposits,
+        cell_deposits,
+        resolution: res as u32,
+    });
+}
+
+// ---------------------------------------------------------------------------
+// Resource overlay (F3)
+// ---------------------------------------------------------------------------
+
+/// Height the overlay floats above the terrain surface, to avoid z-fighting.
+#[cfg(feature = "gui")]
+const OVERLAY_LIFT: f32 = 0.15;
+
+/// Resource overlay state: which resource types are drawn, and the texture
+/// they are painted into (one pixel per heightmap cell).
+#[cfg(feature = "gui")]
+#[derive(Resource)]
+pub struct ResourceOverlay {
+    /// Per-resource filter, indexed by `ResourceType as usize`.
+    pub shown: [bool; ResourceType::ALL.len()],
+    image: Handle<Image>,
+}
+
+#[cfg(feature = "gui")]
+impl Default for ResourceOverlay {
+    fn default() -> Self {
+        Self {
+            shown: [true; ResourceType::ALL.len()],
+            image: Handle::default(),
+        }
+    }
+}
+
+/// Overlay copy of a terrain chunk, drawn just above it with the overlay
+/// texture. `source` is the chunk mesh it mirrors.
+#[cfg(feature = "gui")]
+#[derive(Component)]
+pub struct ResourceOverlayMesh {
+    source: AssetId<Mesh>,
+}
+
+/// The surface of a chunk mesh, without its skirt or vertex colors, so the
+/// overlay texture shows its own colors. Each vertex samples the center of
+/// its cell's texel, `(col + 0.5) / res`, so colors line up with the cells.
+#[cfg(feature = "gui")]
+fn overlay_mesh(chunk_mesh: &Mesh, chunk: &TerrainChunk, res: u32) -> Option<Mesh> {
+    let step = 1 << chunk.lod;
+    let rows = lod_axis(chunk.cells.min_row, chunk.cells.max_row, step);
+    let cols = lod_axis(chunk.cells.min_col, chunk.cells.max_col, step);
+    // `build_chunk_mesh` puts the grid vertices and surface triangles before the skirt
+    let grid = rows.len() * cols.len();
+    let surface = (rows.len() - 1) * (cols.len() - 1) * 6;
+
+    let positions = chunk_mesh.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?.get(..grid)?.to_vec();
+    let normals = chunk_mesh.attribute(Mesh::ATTRIBUTE_NORMAL)?.as_float3()?.get(..grid)?.to_vec();
+    let texel = |i: u32| (i as f32 + 0.5) / res as f32;
+    let uvs: Vec<[f32; 2]> = rows
+        .iter()
+        .flat_map(|&row| cols.iter().map(move |&col| [texel(col), texel(row)]))
+        .collect();
+    let indices: Vec<u32> = chunk_mesh.indices()?.iter().take(surface).map(|i| i as u32).collect();
+
+    Some(
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+            .with_inserted_indices(Indices::U32(indices)),
+    )
+}
+
+/// Paint known resources into RGBA pixels: shown resources' colors mixed
+/// by richness, with alpha from the richest. Hidden and filtered-out
+/// resources are transparent.
+#[cfg(feature = "gui")]
+fn paint_overlay(resource_map: &ResourceMap, overlay: &ResourceOverlay) -> Vec<u8> {
+    let res = resource_map.resolution;
+    let mut pixels = Vec::with_capacity((res * res * 4) as usize);
+    for row in 0..res {
+        for col in 0..res {
+            let shown = resource_map
+                .known(row, col)
+                .filter(|entry| overlay.shown[entry.resource as usize]);
+            match resource_tint(shown) {
+                Some([r, g, b, richness]) => {
+                    let alpha = richness * 0.85;
+                    pixels.extend([r, g, b, alpha].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+                }
+                None => pixels.extend([0; 4]),
             }
+        }
+    }
+    pixels
+}
 
-            let x = (col as f32 + 0.5) * cell_size - half;
-            let z = (row as f32 + 0.5) * cell_size - half;
-            let y = heightmap.sample_world(x, z, config.map_size) + 0.3;
+/// Create the overlay texture and drape one overlay mesh over each terrain
+/// chunk, as a child of the chunk. Runs in `GenerateWorld` after the terrain
+/// chunks are spawned.
+#[cfg(feature = "gui")]
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_resource_overlay(
+    mut commands: Commands,
+    visible: Res<crate::ui::DebugVisible>,
+    resource_map: Res<ResourceMap>,
+    mut overlay: ResMut<ResourceOverlay>,
+    chunks: Query<(Entity, &TerrainChunk, &Mesh3d)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let res = resource_map.resolution;
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: res,
+            height: res,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.data = Some(paint_overlay(&resource_map, &overlay));
+    overlay.image = images.add(image);
+
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(overlay.image.clone()),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    let visibility = if visible.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for (entity, chunk, chunk_mesh) in &chunks {
+        let Some(mesh) = meshes.get(&chunk_mesh.0).and_then(|mesh| overlay_mesh(mesh, chunk, res)) else {
+            continue;
+        };
+        commands.entity(entity).with_child((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material.clone()),
+            Transform::from_xyz(0.0, OVERLAY_LIFT, 0.0),
+            visibility,
+            NotShadowCaster,
+            ResourceOverlayMesh {
+                source: chunk_mesh.id(),
+            },
+        ));
+    }
+}
 
-            gizmos.rect(
-                Isometry3d::new(Vec3::new(x, y, z), flat_rot),
-                rect_size,
-                yellow,
-            );
+/// Keep the overlay in sync: show it with the F3 debug toggle, follow terrain
+/// chunk rebuilds (LOD changes, terraform edits), and repaint the texture only
+/// while shown, when it is turned on or the resource map or filter changed.
+#[cfg(feature = "gui")]
+#[allow(clippy::too_many_arguments)]
+pub fn update_resource_overlay(
+    visible: Res<crate::ui::DebugVisible>,
+    overlay: Res<ResourceOverlay>,
+    resource_map: Res<ResourceMap>,
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    mut overlay_meshes: Query<(&ResourceOverlayMesh, &ChildOf, &Mesh3d, &mut Visibility)>,
+    chunks: Query<&TerrainChunk>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let modified: HashSet<AssetId<Mesh>> = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (overlay_mesh_source, child_of, mesh_handle, mut visibility) in &mut overlay_meshes {
+        if visible.is_changed() {
+            *visibility = if visible.0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        if modified.contains(&overlay_mesh_source.source)
+            && let Ok(chunk) = chunks.get(child_of.parent())
+            && let Some(mesh) = meshes
+                .get(overlay_mesh_source.source)
+                .and_then(|mesh| overlay_mesh(mesh, chunk, resource_map.resolution))
+            && let Some(target) = meshes.get_mut(&mesh_handle.0)
+        {
+            *target = mesh;
+        }
+    }
+
+    // Changes made while hidden are picked up when the overlay is shown again
+    if visible.0
+        && (visible.is_changed() || resource_map.is_changed() || overlay.is_changed())
+        && let Some(image) = images.get_mut(&overlay.image)
+    {
+        image.data = Some(paint_overlay(&resource_map, &overlay));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn entry(resource: ResourceType, richness: f32) -> ResourceEntry {
+        ResourceEntry {
+            resource,
+            richness,
+            capacity: richness,
         }
     }
+
+    /// Square map from the entries of each (row, col) cell.
+    fn map_from(res: u32, entries: impl Fn(u32, u32) -> Vec<ResourceEntry>) -> ResourceMap {
+        let cells = (0..res * res)
+            .map(|i| ResourceCell {
+                entries: entries(i / res, i % res),
+            })
+            .collect();
+        ResourceMap::new(cells, res)
+    }
+
+    fn richness(map: &ResourceMap, row: u32, col: u32, resource: ResourceType) -> Option<f32> {
+        map.get(row, col).get(resource).map(|entry| entry.richness)
+    }
+
+    #[test]
+    fn extraction_clamps_at_zero() {
+        let mut map = map_from(2, |_, _| vec![entry(ResourceType::Coal, 0.3)]);
+
+        assert!((map.extract(0, 0, ResourceType::Coal, 0.1).unwrap() - 0.2).abs() < 1e-6);
+        assert_eq!(map.extract(0, 0, ResourceType::Coal, 0.5), Some(0.0));
+        assert_eq!(map.extract(0, 0, ResourceType::Coal, 0.5), Some(0.0));
+        assert_eq!(richness(&map, 0, 0, ResourceType::Coal), Some(0.0));
+        // Cells without the resource report nothing to extract
+        assert_eq!(map.extract(0, 0, ResourceType::Timber, 0.1), None);
+        // Other cells are untouched, and coal never regrows
+        assert_eq!(richness(&map, 1, 1, ResourceType::Coal), Some(0.3));
+        assert!(!map.is_regrowing());
+    }
+
+    #[test]
+    fn regrowth_only_touches_renewables_below_capacity() {
+        let mut map = map_from(2, |row, col| match (row, col) {
+            (0, 0) | (0, 1) => vec![entry(ResourceType::Timber, 0.8)],
+            _ => vec![entry(ResourceType::Coal, 0.8)],
+        });
+        map.extract(0, 0, ResourceType::Timber, 0.5);
+        map.extract(1, 0, ResourceType::Coal, 0.5);
+        assert!(map.is_regrowing());
+        assert_eq!(map.regrowing, HashSet::from([0]));
+
+        let rate = ResourceType::Timber.regrowth_rate();
+        map.regrow(10.0);
+        assert!((richness(&map, 0, 0, ResourceType::Timber).unwrap() - (0.3 + rate * 10.0)).abs() < 1e-6);
+        assert_eq!(richness(&map, 0, 1, ResourceType::Timber), Some(0.8));
+        assert!((richness(&map, 1, 0, ResourceType::Coal).unwrap() - 0.3).abs() < 1e-6);
+
+        // Stops at capacity and drops out of the regrowing set
+        map.regrow(1000.0);
+        assert_eq!(richness(&map, 0, 0, ResourceType::Timber), Some(0.8));
+        assert!(!map.is_regrowing());
+    }
+
+    #[test]
+    fn cell_holds_resources_independently() {
+        let mut map = map_from(2, |_, _| vec![entry(ResourceType::Coal, 0.8), entry(ResourceType::Timber, 0.6)]);
+
+        assert!((map.extract(0, 0, ResourceType::Timber, 0.5).unwrap() - 0.1).abs() < 1e-6);
+        assert_eq!(richness(&map, 0, 0, ResourceType::Coal), Some(0.8));
+        assert!((map.extract(0, 0, ResourceType::Coal, 0.2).unwrap() - 0.6).abs() < 1e-6);
+        assert!((richness(&map, 0, 0, ResourceType::Timber).unwrap() - 0.1).abs() < 1e-6);
+        assert_eq!(map.get(0, 0).entries.len(), 2);
+
+        // Coal stays hidden until surveyed; timber shows from the start
+        let known: Vec<ResourceType> = map.known(0, 0).map(|entry| entry.resource).collect();
+        assert_eq!(known, vec![ResourceType::Timber]);
+        map.surveyed[0] = true;
+        assert_eq!(map.known(0, 0).count(), 2);
+    }
+
+    #[test]
+    fn adjacent_patches_form_separate_deposits() {
+        // Coal in the west half, clay in the east half, timber over both
+        let map = map_from(4, |_, col| {
+            let ground = if col < 2 { ResourceType::Coal } else { ResourceType::Clay };
+            vec![entry(ground, 0.5), entry(ResourceType::Timber, 0.25)]
+        });
+        let mut world = World::new();
+        world.insert_resource(TerrainConfig {
+            map_size: 40.0,
+            ..default()
+        });
+        world.insert_resource(map);
+        world.run_system_once(generate_resource_deposits).unwrap();
+        let deposits = world.resource::<ResourceDeposits>();
+
+        assert_eq!(deposits.deposits.len(), 3);
+        let coal = deposits.deposit_at(0, 0, ResourceType::Coal).unwrap();
+        let clay = deposits.deposit_at(3, 3, ResourceType::Clay).unwrap();
+        let timber = deposits.deposit_at(0, 0, ResourceType::Timber).unwrap();
+        assert_ne!(coal.id, clay.id);
+        assert_eq!(coal.cells.len(), 8);
+        assert_eq!(clay.cells.len(), 8);
+        assert!((coal.total_reserves - 4.0).abs() < 1e-5);
+        assert_eq!((coal.bounds.min_col, coal.bounds.max_col), (0, 1));
+        assert_eq!((clay.bounds.min_col, clay.bounds.max_col), (2, 3));
+        assert!(deposits.deposit_at(0, 3, ResourceType::Coal).is_none());
+
+        // The timber over both patches is one deposit of its own
+        assert_eq!(timber.cells.len(), 16);
+        assert_eq!(deposits.deposit_at(3, 3, ResourceType::Timber).unwrap().id, timber.id);
+    }
 }
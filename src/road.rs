@@ -1,16 +1,24 @@
 use bevy::prelude::*;
+#[cfg(feature = "gui")]
 use bevy::asset::RenderAssetUsages;
+#[cfg(feature = "gui")]
 use bevy::mesh::{Indices, PrimitiveTopology};
-use bevy::picking::mesh_picking::ray_cast::{MeshRayCast, MeshRayCastSettings};
+#[cfg(feature = "gui")]
 use bevy_egui::input::EguiWantsInput;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::terrain::{Heightmap, TerrainConfig, TerrainMesh};
-use crate::ui::Notifications;
+use crate::spatial::{xz_bounds, SpatialGrid};
+use crate::terrain::Heightmap;
+#[cfg(feature = "gui")]
+use crate::terrain::TerrainConfig;
+#[cfg(feature = "gui")]
+use crate::notifications::Notifications;
+#[cfg(feature = "gui")]
+use crate::ui::CursorWorldPosition;
 
 /// Surface material of a road. Affects cost, speed, and visuals.
-/// Only Dirt is used initially — the others exist for future upgrade progression.
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
+/// New roads are Dirt; the upgrade tool promotes them to Gravel, then Paved.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub enum RoadType {
     #[default]
     Dirt,
@@ -20,13 +28,59 @@ pub enum RoadType {
 
 impl RoadType {
     /// Vertex color for this road surface type.
-    fn color(&self) -> [f32; 4] {
+    pub fn color(&self) -> [f32; 4] {
         match self {
             RoadType::Dirt   => [0.35, 0.25, 0.15, 1.0],
             RoadType::Gravel => [0.60, 0.58, 0.55, 1.0],
             RoadType::Paved  => [0.35, 0.35, 0.38, 1.0],
         }
     }
+
+    /// Travel speed relative to a dirt road. Edge costs in route searches
+    /// divide segment length by this, giving the travel cost modifiers from
+    /// the economic design (Dirt 1.0, Gravel 0.7, Paved 0.4).
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            RoadType::Dirt   => 1.0,
+            RoadType::Gravel => 1.0 / 0.7,
+            RoadType::Paved  => 1.0 / 0.4,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RoadType::Dirt   => "Dirt",
+            RoadType::Gravel => "Gravel",
+            RoadType::Paved  => "Paved",
+        }
+    }
+
+    /// The surface this one can be upgraded to, if any.
+    pub fn upgrade(&self) -> Option<RoadType> {
+        match self {
+            RoadType::Dirt   => Some(RoadType::Gravel),
+            RoadType::Gravel => Some(RoadType::Paved),
+            RoadType::Paved  => None,
+        }
+    }
+
+    /// Funds per world unit of road length to upgrade to this surface.
+    pub fn cost_per_unit(&self) -> f32 {
+        match self {
+            RoadType::Dirt   => 0.0,
+            RoadType::Gravel => 4.0,
+            RoadType::Paved  => 10.0,
+        }
+    }
+
+    /// Game seconds per world unit of road length to upgrade to this surface.
+    pub fn build_time_per_unit(&self) -> f32 {
+        match self {
+            RoadType::Dirt   => 0.0,
+            RoadType::Gravel => 0.2,
+            RoadType::Paved  => 0.4,
+        }
+    }
 }
 
 /// Unique identifier for a node in the road network.
@@ -67,13 +121,75 @@ impl Default for RoadSegment {
     }
 }
 
+/// A segment replaced by two halves in `RoadNetwork::split_segment_at`.
+pub struct SegmentSplit {
+    pub original: SegmentId,
+    pub halves: [SegmentId; 2],
+    /// Share of the combined length in each half, summing to 1.
+    pub shares: [f32; 2],
+}
+
+/// Where a placed road point attaches to the existing network.
+#[derive(Clone, Copy, Debug)]
+pub enum RoadSnap {
+    /// An existing node (junction or dead end).
+    Node(NodeId),
+    /// A point partway along a segment, which becomes a T-junction on commit.
+    Segment { segment: SegmentId, point: Vec3 },
+}
+
+impl RoadSnap {
+    /// World position the point snaps to.
+    pub fn position(&self, network: &RoadNetwork) -> Option<Vec3> {
+        match *self {
+            RoadSnap::Node(id) => network.node(id).map(|node| node.position),
+            RoadSnap::Segment { point, .. } => Some(point),
+        }
+    }
+}
+
+/// Cell size (world units) of the spatial grids indexing road nodes and segments.
+const ROAD_GRID_CELL_SIZE: f32 = 32.0;
+
 /// The road network graph. Stores all nodes and segments, queryable by ID.
-#[derive(Resource, Default)]
+///
+/// Also keeps each segment's sampled spline and spatial grids of nodes and
+/// segments (by spline bounds), updated on every mutation, so geometric
+/// queries only look at roads near the point of interest.
+///
+/// Every mutation records the segments and nodes it added, removed or
+/// reshaped, so meshes can be rebuilt for just those (see `take_changes`).
+/// Splits are recorded too, so state keyed by segment can follow a road onto
+/// its halves (see `take_splits`).
+#[derive(Resource)]
 pub struct RoadNetwork {
     nodes: HashMap<NodeId, RoadNode>,
     segments: HashMap<SegmentId, RoadSegment>,
     next_node_id: u32,
     next_segment_id: u32,
+    curves: HashMap<SegmentId, Vec<Vec3>>,
+    node_grid: SpatialGrid<NodeId>,
+    segment_grid: SpatialGrid<SegmentId>,
+    changed_segments: HashSet<SegmentId>,
+    changed_nodes: HashSet<NodeId>,
+    splits: Vec<SegmentSplit>,
+}
+
+impl Default for RoadNetwork {
+    fn default() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            segments: HashMap::new(),
+            next_node_id: 0,
+            next_segment_id: 0,
+            curves: HashMap::new(),
+            node_grid: SpatialGrid::new(ROAD_GRID_CELL_SIZE),
+            segment_grid: SpatialGrid::new(ROAD_GRID_CELL_SIZE),
+            changed_segments: HashSet::new(),
+            changed_nodes: HashSet::new(),
+            splits: Vec::new(),
+        }
+    }
 }
 
 impl RoadNetwork {
@@ -85,6 +201,9 @@ impl RoadNetwork {
             position,
             segments: Vec::new(),
         });
+        let xz = Vec2::new(position.x, position.z);
+        self.node_grid.insert(id, xz, xz);
+        self.changed_nodes.insert(id);
         id
     }
 
@@ -114,10 +233,30 @@ impl RoadNetwork {
         if let Some(node) = self.nodes.get_mut(&to) {
             node.segments.push(id);
         }
+        self.index_segment(id);
 
         id
     }
 
+    /// Resample a segment's spline, re-file it in the segment grid and record
+    /// it (and its nodes) as changed.
+    fn index_segment(&mut self, id: SegmentId) {
+        let Some(segment) = self.segments.get(&id) else { return };
+        self.changed_segments.insert(id);
+        self.changed_nodes.extend(segment.nodes);
+        let (Some(a), Some(b)) = (self.nodes.get(&segment.nodes[0]), self.nodes.get(&segment.nodes[1])) else {
+            return;
+        };
+        let mut path = vec![a.position];
+        path.extend_from_slice(&segment.control_points);
+        path.push(b.position);
+        let curve = sample_catmull_rom(&path, MESH_SAMPLES_PER_SEGMENT);
+
+        let (min, max) = xz_bounds(curve.iter().copied(), segment.width / 2.0);
+        self.segment_grid.insert(id, min, max);
+        self.curves.insert(id, curve);
+    }
+
     pub fn node(&self, id: NodeId) -> Option<&RoadNode> {
         self.nodes.get(&id)
     }
@@ -134,9 +273,64 @@ impl RoadNetwork {
         &self.segments
     }
 
+    /// Segments and nodes added, removed or modified since the last call,
+    /// clearing the record. Call through `bypass_change_detection` from a
+    /// system so taking the changes doesn't itself flag the resource changed.
+    pub fn take_changes(&mut self) -> (HashSet<SegmentId>, HashSet<NodeId>) {
+        (std::mem::take(&mut self.changed_segments), std::mem::take(&mut self.changed_nodes))
+    }
+
+    /// Segments split since the last call, oldest first, clearing the record.
+    /// A half may itself appear as the original of a later split.
+    pub fn take_splits(&mut self) -> Vec<SegmentSplit> {
+        std::mem::take(&mut self.splits)
+    }
+
+    /// Spline through a segment's endpoints and control points, sampled at
+    /// mesh resolution. `None` if the segment or either endpoint is missing.
+    pub fn segment_curve(&self, id: SegmentId) -> Option<&[Vec3]> {
+        self.curves.get(&id).map(Vec::as_slice)
+    }
+
+    /// Segments whose spline (widened by half the road width) may overlap the
+    /// (x, z) box `min..max`.
+    pub fn segments_in_aabb(&self, min: Vec2, max: Vec2) -> Vec<SegmentId> {
+        self.segment_grid.query_aabb(min, max)
+    }
+
+    /// Segments whose spline (widened by half the road width) may come within
+    /// `radius` of an (x, z) point.
+    pub fn segments_near(&self, center: Vec2, radius: f32) -> Vec<SegmentId> {
+        self.segment_grid.query_radius(center, radius)
+    }
+
+    /// Length of a segment along its spline, in world units.
+    pub fn segment_length(&self, id: SegmentId) -> Option<f32> {
+        let curve = self.segment_curve(id)?;
+        Some(curve.windows(2).map(|pair| pair[0].distance(pair[1])).sum())
+    }
+
+    /// Change a segment's surface. Returns false if the segment does not exist.
+    pub fn set_road_type(&mut self, id: SegmentId, road_type: RoadType) -> bool {
+        match self.segments.get_mut(&id) {
+            Some(segment) => {
+                segment.road_type = road_type;
+                // Junction colors follow the surfaces meeting there
+                self.changed_segments.insert(id);
+                self.changed_nodes.extend(segment.nodes);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Remove a segment and unregister it from its endpoint nodes.
     pub fn remove_segment(&mut self, id: SegmentId) {
+        self.curves.remove(&id);
+        self.segment_grid.remove(id);
         if let Some(segment) = self.segments.remove(&id) {
+            self.changed_segments.insert(id);
+            self.changed_nodes.extend(segment.nodes);
             for node_id in &segment.nodes {
                 if let Some(node) = self.nodes.get_mut(node_id) {
                     node.segments.retain(|&s| s != id);
@@ -145,29 +339,157 @@ impl RoadNetwork {
         }
     }
 
-    /// Split an existing segment at a position, creating a new intersection node
-    /// and two sub-segments that replace the original. Returns the new node ID.
+    /// Remove every node no longer connected to any segment, e.g. the loose
+    /// ends left after bulldozing a road. Returns how many were removed.
+    pub fn remove_orphan_nodes(&mut self) -> usize {
+        let orphans: Vec<NodeId> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.segments.is_empty())
+            .map(|(&id, _)| id)
+            .collect();
+        for &id in &orphans {
+            self.nodes.remove(&id);
+            self.node_grid.remove(id);
+            self.changed_nodes.insert(id);
+        }
+        orphans.len()
+    }
+
+    /// Split an existing segment at a position on its spline, creating a new
+    /// intersection node and two sub-segments that replace the original.
+    /// Control points before the split stay with the first half and the rest go
+    /// to the second, so a curved road keeps its shape. The split is recorded
+    /// for `take_splits`. Returns the new node ID.
     pub fn split_segment_at(&mut self, segment_id: SegmentId, position: Vec3) -> NodeId {
-        let (nodes, road_type, width) = {
+        // The spline span (between consecutive control path points) holding the split
+        let span = self
+            .segment_curve(segment_id)
+            .and_then(|curve| closest_on_polyline(curve, Vec2::new(position.x, position.z)))
+            .map_or(0, |(piece, _, _)| piece / MESH_SAMPLES_PER_SEGMENT);
+
+        let (nodes, control_points, road_type, width) = {
             let segment = &self.segments[&segment_id];
-            (segment.nodes, segment.road_type, segment.width)
+            (segment.nodes, segment.control_points.clone(), segment.road_type, segment.width)
         };
 
         self.remove_segment(segment_id);
 
+        let (before, after) = control_points.split_at(span.min(control_points.len()));
         let mid_node = self.add_node(position);
-        self.add_segment(nodes[0], mid_node, Vec::new(), road_type, width);
-        self.add_segment(mid_node, nodes[1], Vec::new(), road_type, width);
+        let first = self.add_segment(nodes[0], mid_node, before.to_vec(), road_type, width);
+        let second = self.add_segment(mid_node, nodes[1], after.to_vec(), road_type, width);
+
+        let lengths = [first, second].map(|id| self.segment_length(id).unwrap_or(0.0));
+        let total = lengths[0] + lengths[1];
+        let shares = if total > 0.0 { lengths.map(|length| length / total) } else { [0.5, 0.5] };
+        self.splits.push(SegmentSplit { original: segment_id, halves: [first, second], shares });
 
         mid_node
     }
 
+    /// Re-seat nodes and control points inside a world-space (x, z) rectangle
+    /// onto the terrain after the heightmap under them changed. Only segments
+    /// with a point that actually moved are resampled and queued for a mesh
+    /// rebuild. Returns true if there were any.
+    pub fn drape_region(&mut self, heightmap: &Heightmap, map_size: f32, min: Vec2, max: Vec2) -> bool {
+        let inside = |p: Vec3| p.x >= min.x && p.x <= max.x && p.z >= min.y && p.z <= max.y;
+        // Re-seat a point, reporting whether its height changed
+        let reseat = |p: &mut Vec3| {
+            if !inside(*p) {
+                return false;
+            }
+            let y = heightmap.sample_world(p.x, p.z, map_size);
+            let moved = y != p.y;
+            p.y = y;
+            moved
+        };
+
+        let mut moved_nodes = HashSet::new();
+        for id in self.node_grid.query_aabb(min, max) {
+            if let Some(node) = self.nodes.get_mut(&id)
+                && reseat(&mut node.position)
+            {
+                moved_nodes.insert(id);
+            }
+        }
+
+        // Only segments whose bounds reach the rectangle can have moved points.
+        // Draping only changes heights, so their XZ bounds stay valid.
+        let mut moved_segments = Vec::new();
+        for id in self.segments_in_aabb(min, max) {
+            let Some(segment) = self.segments.get_mut(&id) else { continue };
+            let mut moved = segment.nodes.iter().any(|node| moved_nodes.contains(node));
+            for point in &mut segment.control_points {
+                moved |= reseat(point);
+            }
+            if moved {
+                moved_segments.push(id);
+            }
+        }
+
+        for &id in &moved_segments {
+            self.index_segment(id);
+        }
+        !moved_segments.is_empty()
+    }
+
+    /// Closest point on any segment's spline within `max_distance` of an (x, z)
+    /// position, as the segment and the fraction of its length (0..1) at which
+    /// the point lies.
+    pub fn nearest_segment_point(&self, position: Vec2, max_distance: f32) -> Option<(SegmentId, f32)> {
+        let mut best: Option<(SegmentId, f32, f32)> = None;
+
+        for id in self.segments_near(position, max_distance) {
+            let Some(curve) = self.segment_curve(id) else { continue };
+            let Some((piece, s, dist)) = closest_on_polyline(curve, position) else { continue };
+            if dist <= max_distance && best.is_none_or(|(_, _, d)| dist < d) {
+                let fractions = arc_fractions(curve);
+                let t = fractions[piece] + (fractions[piece + 1] - fractions[piece]) * s;
+                best = Some((id, t, dist));
+            }
+        }
+
+        best.map(|(id, t, _)| (id, t))
+    }
+
+    /// What a road point placed at `position` would attach to: the nearest node
+    /// within `max_distance` if any, else the nearest point on a segment's spline
+    /// within `max_distance`.
+    pub fn snap(&self, position: Vec3, max_distance: f32) -> Option<RoadSnap> {
+        if let Some(node) = self.nearest_node(position, max_distance) {
+            return Some(RoadSnap::Node(node));
+        }
+
+        let target = Vec2::new(position.x, position.z);
+        let mut best: Option<(SegmentId, Vec3, f32)> = None;
+        for id in self.segments_near(target, max_distance) {
+            let Some(curve) = self.segment_curve(id) else { continue };
+            let Some((piece, s, dist)) = closest_on_polyline(curve, target) else { continue };
+            if dist <= max_distance && best.is_none_or(|(_, _, d)| dist < d) {
+                best = Some((id, curve[piece].lerp(curve[piece + 1], s), dist));
+            }
+        }
+        best.map(|(segment, point, _)| RoadSnap::Segment { segment, point })
+    }
+
+    /// Node for a committed road endpoint: the snapped node, a new T-junction
+    /// splitting the snapped segment, or a new free node.
+    fn attach(&mut self, position: Vec3) -> NodeId {
+        match self.snap(position, SNAP_RADIUS) {
+            Some(RoadSnap::Node(node)) => node,
+            Some(RoadSnap::Segment { segment, point }) => self.split_segment_at(segment, point),
+            None => self.add_node(position),
+        }
+    }
+
     /// Find the nearest node within a radius. Used for snap-to-existing behavior.
     pub fn nearest_node(&self, position: Vec3, max_distance: f32) -> Option<NodeId> {
         let max_dist_sq = max_distance * max_distance;
         let mut best: Option<(NodeId, f32)> = None;
 
-        for (&id, node) in &self.nodes {
+        for id in self.node_grid.query_radius(Vec2::new(position.x, position.z), max_distance) {
+            let Some(node) = self.nodes.get(&id) else { continue };
             let dist_sq = node.position.distance_squared(position);
             if dist_sq < max_dist_sq {
                 if best.is_none() || dist_sq < best.unwrap().1 {
@@ -221,194 +543,501 @@ pub fn sample_catmull_rom(points: &[Vec3], samples_per_segment: usize) -> Vec<Ve
     result
 }
 
-/// Marker component for the generated road mesh entity.
+/// Closest point of a polyline to an (x, z) position, as the piece index, the
+/// fraction along that piece, and the distance. `None` for fewer than two points.
+fn closest_on_polyline(points: &[Vec3], position: Vec2) -> Option<(usize, f32, f32)> {
+    let mut best: Option<(usize, f32, f32)> = None;
+    for (i, pair) in points.windows(2).enumerate() {
+        let a = Vec2::new(pair[0].x, pair[0].z);
+        let b = Vec2::new(pair[1].x, pair[1].z);
+        let ab = b - a;
+        let s = if ab.length_squared() > 0.0 {
+            ((position - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let dist = position.distance(a + ab * s);
+        if best.is_none_or(|(_, _, d)| dist < d) {
+            best = Some((i, s, dist));
+        }
+    }
+    best
+}
+
+/// Fraction of the total polyline length (0..1) reached at each point of a
+/// sampled curve.
+pub fn arc_fractions(points: &[Vec3]) -> Vec<f32> {
+    let mut cumulative = Vec::with_capacity(points.len());
+    let mut length = 0.0;
+    for (i, &point) in points.iter().enumerate() {
+        if i > 0 {
+            length += points[i - 1].distance(point);
+        }
+        cumulative.push(length);
+    }
+    if length > 0.0 {
+        for value in &mut cumulative {
+            *value /= length;
+        }
+    }
+    cumulative
+}
+
+/// Marker component for generated road mesh entities (segment strips,
+/// junctions and dead-end caps).
+#[cfg(feature = "gui")]
 #[derive(Component)]
 pub struct RoadMesh;
 
+/// Road mesh entities, one per segment strip and one per node (junction
+/// polygon or dead-end cap), so edits only rebuild the pieces they affect.
+#[cfg(feature = "gui")]
+#[derive(Resource, Default)]
+pub struct RoadMeshes {
+    segments: HashMap<SegmentId, Entity>,
+    junctions: HashMap<NodeId, Entity>,
+    /// Shared by every road mesh; colors come from vertex colors.
+    material: Option<Handle<StandardMaterial>>,
+}
+
 /// Small Y offset above terrain to prevent z-fighting.
+#[cfg(feature = "gui")]
 const ROAD_Y_OFFSET: f32 = 0.15;
 
 /// Number of curve samples per spline segment for mesh generation.
 const MESH_SAMPLES_PER_SEGMENT: usize = 8;
 
-/// Rebuild road meshes whenever the road network changes.
+/// Sides used to round the cap at a dead end.
+#[cfg(feature = "gui")]
+const DEAD_END_CAP_SIDES: u32 = 8;
+
+/// Upper bound on how far a segment strip is trimmed back from a junction, so
+/// roads meeting at very sharp angles don't lose most of their strip.
+#[cfg(feature = "gui")]
+const MAX_JUNCTION_TRIM: f32 = 6.0;
+
+/// Rebuild the road meshes affected by the latest road network changes.
 ///
-/// For each segment: samples the Catmull-Rom spline, generates a flat strip
-/// of vertices projected onto the terrain heightmap, and stitches them into
-/// triangles. Vertex colors are driven by road type.
+/// Each segment's sampled spline becomes a flat strip of cross-sections
+/// projected onto the terrain heightmap, trimmed back where it meets other
+/// segments. Nodes joining two or more segments get a junction polygon that
+/// fills the space between the trimmed strips, and dead ends get a rounded
+/// cap. Vertex colors are driven by road type.
+///
+/// Only changed segments and nodes are rebuilt, plus every segment meeting a
+/// changed node, since a node's arms set the trims of all its strips.
+#[cfg(feature = "gui")]
 pub fn generate_road_meshes(
     mut commands: Commands,
-    road_network: Res<RoadNetwork>,
+    mut road_network: ResMut<RoadNetwork>,
+    mut road_meshes: ResMut<RoadMeshes>,
     heightmap: Res<Heightmap>,
     config: Res<TerrainConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    existing: Query<Entity, With<RoadMesh>>,
 ) {
     if !road_network.is_changed() {
         return;
     }
+    let (mut changed_segments, changed_nodes) = road_network.bypass_change_detection().take_changes();
+    for node_id in &changed_nodes {
+        if let Some(node) = road_network.node(*node_id) {
+            changed_segments.extend(node.segments.iter().copied());
+        }
+    }
+    if changed_segments.is_empty() && changed_nodes.is_empty() {
+        return;
+    }
 
-    // Despawn existing road mesh
-    for entity in &existing {
-        commands.entity(entity).despawn();
+    let material = road_meshes
+        .material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                perceptual_roughness: 0.9,
+                ..default()
+            })
+        })
+        .clone();
+
+    for id in changed_segments {
+        if let Some(entity) = road_meshes.segments.remove(&id) {
+            commands.entity(entity).despawn();
+        }
+        let (Some(segment), Some(curve)) = (road_network.segment(id), trimmed_curve(&road_network, id)) else {
+            continue;
+        };
+        let mut buffers = RoadMeshBuffers::default();
+        buffers.push_strip(&curve, segment.width / 2.0, segment.road_type.color(), &heightmap, config.map_size);
+        if let Some(entity) = buffers.spawn(&mut commands, &mut meshes, &material) {
+            road_meshes.segments.insert(id, entity);
+        }
     }
 
-    if road_network.segments().is_empty() {
-        return;
+    for node_id in changed_nodes {
+        if let Some(entity) = road_meshes.junctions.remove(&node_id) {
+            commands.entity(entity).despawn();
+        }
+        let mut buffers = RoadMeshBuffers::default();
+        buffers.push_junction(&road_network, node_id, &heightmap, config.map_size);
+        if let Some(entity) = buffers.spawn(&mut commands, &mut meshes, &material) {
+            road_meshes.junctions.insert(node_id, entity);
+        }
     }
+}
 
-    let mut positions: Vec<[f32; 3]> = Vec::new();
-    let mut normals: Vec<[f32; 3]> = Vec::new();
-    let mut uvs: Vec<[f32; 2]> = Vec::new();
-    let mut colors: Vec<[f32; 4]> = Vec::new();
-    let mut indices: Vec<u32> = Vec::new();
+// ---------------------------------------------------------------------------
+// Junction geometry
+// ---------------------------------------------------------------------------
+
+/// One segment end meeting a node.
+#[cfg(feature = "gui")]
+struct JunctionArm {
+    segment: SegmentId,
+    /// Which end of the segment (index into `RoadSegment::nodes`) is at the node.
+    end: usize,
+    /// Unit (x, z) direction leaving the node along the segment.
+    direction: Vec2,
+    half_width: f32,
+    /// Distance along the segment from the node to where its strip starts.
+    trim: f32,
+}
 
-    for segment in road_network.segments().values() {
-        let Some(node_a) = road_network.node(segment.nodes[0]) else { continue };
-        let Some(node_b) = road_network.node(segment.nodes[1]) else { continue };
+/// Left-hand normal of an (x, z) direction, i.e. rotated counterclockwise
+/// when viewed from above.
+#[cfg(feature = "gui")]
+fn left_of(direction: Vec2) -> Vec2 {
+    Vec2::new(-direction.y, direction.x)
+}
 
-        // Build path and sample the spline
-        let mut path = vec![node_a.position];
-        path.extend_from_slice(&segment.control_points);
-        path.push(node_b.position);
+/// Length of a polyline measured in the XZ plane.
+#[cfg(feature = "gui")]
+fn xz_length(points: &[Vec3]) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| Vec2::new(pair[1].x - pair[0].x, pair[1].z - pair[0].z).length())
+        .sum()
+}
+
+/// The segment ends meeting at a node, sorted counterclockwise by direction,
+/// with each arm's trim set so neighbouring strips meet edge to edge instead
+/// of overlapping.
+///
+/// Adjacent arms less than half a turn apart are trimmed back to where their
+/// facing edges cross. A dead end (single arm) is not trimmed.
+#[cfg(feature = "gui")]
+fn junction_arms(network: &RoadNetwork, node_id: NodeId) -> Vec<JunctionArm> {
+    let Some(node) = network.node(node_id) else {
+        return Vec::new();
+    };
 
-        let curve_points = sample_catmull_rom(&path, MESH_SAMPLES_PER_SEGMENT);
-        if curve_points.len() < 2 {
+    let mut seen: Vec<SegmentId> = Vec::new();
+    let mut arms: Vec<(JunctionArm, f32)> = Vec::new();
+    for &seg_id in &node.segments {
+        if seen.contains(&seg_id) {
             continue;
         }
-
-        let half_width = segment.width / 2.0;
-        let color = segment.road_type.color();
-        let base_vertex = positions.len() as u32;
-
-        for (i, &center) in curve_points.iter().enumerate() {
-            // Forward direction (tangent along the road)
-            let forward = if i < curve_points.len() - 1 {
-                (curve_points[i + 1] - center).normalize_or_zero()
+        seen.push(seg_id);
+        let (Some(segment), Some(curve)) = (network.segment(seg_id), network.segment_curve(seg_id)) else {
+            continue;
+        };
+        if curve.len() < 2 {
+            continue;
+        }
+        let max_trim = (xz_length(curve) * 0.45).min(MAX_JUNCTION_TRIM);
+        for end in 0..2 {
+            if segment.nodes[end] != node_id {
+                continue;
+            }
+            let (from, to) = if end == 0 {
+                (curve[0], curve[1])
             } else {
-                (center - curve_points[i - 1]).normalize_or_zero()
+                (curve[curve.len() - 1], curve[curve.len() - 2])
             };
+            let direction = Vec2::new(to.x - from.x, to.z - from.z).normalize_or_zero();
+            if direction == Vec2::ZERO {
+                continue;
+            }
+            let arm = JunctionArm {
+                segment: seg_id,
+                end,
+                direction,
+                half_width: segment.width / 2.0,
+                trim: 0.0,
+            };
+            arms.push((arm, max_trim));
+        }
+    }
 
-            // Right vector perpendicular to forward on the XZ plane
-            let right = Vec3::new(-forward.z, 0.0, forward.x).normalize_or_zero();
+    arms.sort_by(|(a, _), (b, _)| {
+        let angle_a = a.direction.y.atan2(a.direction.x);
+        let angle_b = b.direction.y.atan2(b.direction.x);
+        angle_a.total_cmp(&angle_b)
+    });
+
+    if arms.len() >= 2 {
+        for i in 0..arms.len() {
+            let j = (i + 1) % arms.len();
+            let (a, b) = (&arms[i].0, &arms[j].0);
+            // Only sectors narrower than half a turn have edges that cross in front
+            let cross = a.direction.perp_dot(b.direction);
+            if cross <= 1e-3 {
+                continue;
+            }
+            // Solve a.left_edge(s) = b.right_edge(u) for the distances along each arm
+            let offset = -left_of(b.direction) * b.half_width - left_of(a.direction) * a.half_width;
+            let s = offset.perp_dot(b.direction) / cross;
+            let u = -a.direction.perp_dot(offset) / cross;
+            arms[i].0.trim = arms[i].0.trim.max(s);
+            arms[j].0.trim = arms[j].0.trim.max(u);
+        }
+    }
 
-            let left_pt = center - right * half_width;
-            let right_pt = center + right * half_width;
+    arms.into_iter()
+        .map(|(mut arm, max_trim)| {
+            arm.trim = arm.trim.clamp(0.0, max_trim);
+            arm
+        })
+        .collect()
+}
 
-            // Project onto terrain
-            let left_y = heightmap.sample_world(left_pt.x, left_pt.z, config.map_size) + ROAD_Y_OFFSET;
-            let right_y = heightmap.sample_world(right_pt.x, right_pt.z, config.map_size) + ROAD_Y_OFFSET;
+/// A segment's sampled spline with both ends trimmed back to the junction
+/// boundaries at its nodes.
+#[cfg(feature = "gui")]
+fn trimmed_curve(network: &RoadNetwork, id: SegmentId) -> Option<Vec<Vec3>> {
+    let segment = network.segment(id)?;
+    let curve = network.segment_curve(id)?;
+
+    let mut trims = [0.0; 2];
+    for (end, trim) in trims.iter_mut().enumerate() {
+        *trim = junction_arms(network, segment.nodes[end])
+            .iter()
+            .find(|arm| arm.segment == id && arm.end == end)
+            .map_or(0.0, |arm| arm.trim);
+    }
 
-            positions.push([left_pt.x, left_y, left_pt.z]);
-            positions.push([right_pt.x, right_y, right_pt.z]);
+    let mut trimmed = trim_polyline_start(curve, trims[0]);
+    trimmed.reverse();
+    let mut trimmed = trim_polyline_start(&trimmed, trims[1]);
+    trimmed.reverse();
+    Some(trimmed)
+}
+
+/// Cut `distance` (measured in the XZ plane) off the start of a polyline.
+#[cfg(feature = "gui")]
+fn trim_polyline_start(points: &[Vec3], distance: f32) -> Vec<Vec3> {
+    if distance <= 0.0 {
+        return points.to_vec();
+    }
+    let mut walked = 0.0;
+    for (i, pair) in points.windows(2).enumerate() {
+        let length = Vec2::new(pair[1].x - pair[0].x, pair[1].z - pair[0].z).length();
+        if walked + length > distance {
+            let cut = pair[0].lerp(pair[1], (distance - walked) / length);
+            let mut trimmed = vec![cut];
+            trimmed.extend_from_slice(&points[i + 1..]);
+            return trimmed;
+        }
+        walked += length;
+    }
+    points.last().map(|&p| vec![p]).unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Mesh building
+// ---------------------------------------------------------------------------
+
+/// Vertex and index buffers for road geometry. All vertices face up.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+struct RoadMeshBuffers {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
 
-            normals.push([0.0, 1.0, 0.0]);
-            normals.push([0.0, 1.0, 0.0]);
+#[cfg(feature = "gui")]
+impl RoadMeshBuffers {
+    /// Add a vertex at an (x, z) position, projected onto the terrain.
+    fn push_vertex(&mut self, xz: Vec2, uv: [f32; 2], color: [f32; 4], heightmap: &Heightmap, map_size: f32) -> u32 {
+        let y = heightmap.sample_world(xz.x, xz.y, map_size) + ROAD_Y_OFFSET;
+        self.positions.push([xz.x, y, xz.y]);
+        self.normals.push([0.0, 1.0, 0.0]);
+        self.uvs.push(uv);
+        self.colors.push(color);
+        (self.positions.len() - 1) as u32
+    }
 
-            let v = i as f32 / (curve_points.len() - 1) as f32;
-            uvs.push([0.0, v]);
-            uvs.push([1.0, v]);
+    /// Triangle fan around `center` over `rim`, which runs counterclockwise
+    /// when viewed from above. Open fans (caps) skip the closing triangle.
+    #[allow(clippy::too_many_arguments)]
+    fn push_fan(
+        &mut self,
+        center: Vec2,
+        rim: &[Vec2],
+        closed: bool,
+        radius: f32,
+        color: [f32; 4],
+        heightmap: &Heightmap,
+        map_size: f32,
+    ) {
+        let uv = |p: Vec2| {
+            let d = (p - center) / (2.0 * radius.max(0.01));
+            [0.5 + d.x, 0.5 + d.y]
+        };
+        let hub = self.push_vertex(center, [0.5, 0.5], color, heightmap, map_size);
+        let first = self.positions.len() as u32;
+        for &p in rim {
+            self.push_vertex(p, uv(p), color, heightmap, map_size);
+        }
+        let count = rim.len() as u32;
+        let triangles = if closed { count } else { count.saturating_sub(1) };
+        // Winding: hub, next, current → faces up
+        for i in 0..triangles {
+            let next = (i + 1) % count;
+            self.indices.extend([hub, first + next, first + i]);
+        }
+    }
 
-            colors.push(color);
-            colors.push(color);
+    /// Flat strip of cross-sections along a sampled curve.
+    fn push_strip(&mut self, curve: &[Vec3], half_width: f32, color: [f32; 4], heightmap: &Heightmap, map_size: f32) {
+        if curve.len() < 2 {
+            return;
+        }
+        let base_vertex = self.positions.len() as u32;
+
+        for (i, &center) in curve.iter().enumerate() {
+            // Forward direction (tangent along the road)
+            let forward = if i < curve.len() - 1 {
+                curve[i + 1] - center
+            } else {
+                center - curve[i - 1]
+            };
+            let forward = Vec2::new(forward.x, forward.z).normalize_or_zero();
+            let right = left_of(forward);
+            let center = Vec2::new(center.x, center.z);
+
+            let v = i as f32 / (curve.len() - 1) as f32;
+            self.push_vertex(center - right * half_width, [0.0, v], color, heightmap, map_size);
+            self.push_vertex(center + right * half_width, [1.0, v], color, heightmap, map_size);
         }
 
         // Stitch consecutive cross-sections into triangles
-        let num_samples = curve_points.len() as u32;
+        let num_samples = curve.len() as u32;
         for i in 0..(num_samples - 1) {
             let bl = base_vertex + i * 2;         // bottom-left
             let br = base_vertex + i * 2 + 1;     // bottom-right
             let tl = base_vertex + (i + 1) * 2;   // top-left
             let tr = base_vertex + (i + 1) * 2 + 1; // top-right
 
-            indices.push(bl);
-            indices.push(br);
-            indices.push(tl);
-
-            indices.push(tl);
-            indices.push(br);
-            indices.push(tr);
+            self.indices.extend([bl, br, tl, tl, br, tr]);
         }
     }
 
-    // Fill intersection nodes with a flat disc polygon
-    let disc_sides: u32 = 12;
-    for node in road_network.nodes().values() {
-        if node.segments.len() < 2 {
-            continue;
-        }
-
-        // Disc radius = max half-width of connected segments
-        let radius = node.segments.iter()
-            .filter_map(|&seg_id| road_network.segment(seg_id))
-            .map(|seg| seg.width / 2.0)
-            .fold(0.0f32, f32::max);
-
-        // Color from the first connected segment
-        let color = node.segments.iter()
-            .filter_map(|&seg_id| road_network.segment(seg_id))
-            .map(|seg| seg.road_type.color())
-            .next()
-            .unwrap_or([0.55, 0.40, 0.25, 1.0]);
-
-        let center = node.position;
-        let center_y = heightmap.sample_world(center.x, center.z, config.map_size) + ROAD_Y_OFFSET;
-        let base_vertex = positions.len() as u32;
-
-        // Center vertex
-        positions.push([center.x, center_y, center.z]);
-        normals.push([0.0, 1.0, 0.0]);
-        uvs.push([0.5, 0.5]);
-        colors.push(color);
-
-        // Circumference vertices
-        for i in 0..disc_sides {
-            let angle = (i as f32 / disc_sides as f32) * std::f32::consts::TAU;
-            let x = center.x + angle.cos() * radius;
-            let z = center.z + angle.sin() * radius;
-            let y = heightmap.sample_world(x, z, config.map_size) + ROAD_Y_OFFSET;
-
-            positions.push([x, y, z]);
-            normals.push([0.0, 1.0, 0.0]);
-            uvs.push([0.5 + angle.cos() * 0.5, 0.5 + angle.sin() * 0.5]);
-            colors.push(color);
-        }
+    /// Junction polygon at a node joining two or more segments, or a rounded
+    /// cap at a dead end. Nodes without segments add nothing.
+    ///
+    /// The polygon runs counterclockwise through the trimmed end of each arm's
+    /// strip. Between arms more than half a turn apart it bevels around the
+    /// node through the two road edges' points level with it.
+    fn push_junction(&mut self, network: &RoadNetwork, node_id: NodeId, heightmap: &Heightmap, map_size: f32) {
+        let Some(node) = network.node(node_id) else {
+            return;
+        };
+        let arms = junction_arms(network, node_id);
+        let center = Vec2::new(node.position.x, node.position.z);
+
+        // Each arm's strip end, as (end point, outward direction) on the XZ plane
+        let ends: Vec<(Vec2, Vec2)> = arms
+            .iter()
+            .map(|arm| {
+                let Some(curve) = trimmed_curve(network, arm.segment).filter(|c| c.len() >= 2) else {
+                    return (center + arm.direction * arm.trim, arm.direction);
+                };
+                let (from, to) = if arm.end == 0 {
+                    (curve[0], curve[1])
+                } else {
+                    (curve[curve.len() - 1], curve[curve.len() - 2])
+                };
+                let direction = Vec2::new(to.x - from.x, to.z - from.z).normalize_or(arm.direction);
+                (Vec2::new(from.x, from.z), direction)
+            })
+            .collect();
+
+        match arms.len() {
+            0 => {}
+            1 => {
+                // Half disc continuing the strip edges around the back of the node
+                let arm = &arms[0];
+                let (point, direction) = ends[0];
+                let start = left_of(direction).to_angle();
+                let rim: Vec<Vec2> = (0..=DEAD_END_CAP_SIDES)
+                    .map(|i| {
+                        let angle = start + std::f32::consts::PI * i as f32 / DEAD_END_CAP_SIDES as f32;
+                        point + Vec2::from_angle(angle) * arm.half_width
+                    })
+                    .collect();
+                let color = network.segment(arm.segment).map_or(RoadType::Dirt.color(), |s| s.road_type.color());
+                self.push_fan(point, &rim, false, arm.half_width, color, heightmap, map_size);
+            }
+            _ => {
+                let mut rim: Vec<Vec2> = Vec::new();
+                for (i, arm) in arms.iter().enumerate() {
+                    let (point, direction) = ends[i];
+                    rim.push(point - left_of(direction) * arm.half_width);
+                    rim.push(point + left_of(direction) * arm.half_width);
+
+                    let next = &arms[(i + 1) % arms.len()];
+                    if arm.direction.perp_dot(next.direction) <= 1e-3 {
+                        rim.push(center + left_of(arm.direction) * arm.half_width);
+                        rim.push(center - left_of(next.direction) * next.half_width);
+                    }
+                }
 
-        // Triangle fan (winding: center, next, current → faces up)
-        for i in 0..disc_sides {
-            let next = (i + 1) % disc_sides;
-            indices.push(base_vertex);
-            indices.push(base_vertex + 1 + next);
-            indices.push(base_vertex + 1 + i);
+                // Paint the junction in the best surface meeting there
+                let color = arms
+                    .iter()
+                    .filter_map(|arm| network.segment(arm.segment))
+                    .map(|s| s.road_type)
+                    .max_by(|a, b| a.speed_factor().total_cmp(&b.speed_factor()))
+                    .unwrap_or_default()
+                    .color();
+                let radius = arms.iter().map(|arm| arm.half_width + arm.trim).fold(0.0, f32::max);
+                self.push_fan(center, &rim, true, radius, color, heightmap, map_size);
+            }
         }
     }
 
-    if positions.is_empty() {
-        return;
+    /// Spawn the geometry as a road mesh entity. `None` if there is nothing
+    /// to draw.
+    fn spawn(
+        self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        material: &Handle<StandardMaterial>,
+    ) -> Option<Entity> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, self.colors)
+            .with_inserted_indices(Indices::U32(self.indices));
+        let entity = commands
+            .spawn((Mesh3d(meshes.add(mesh)), MeshMaterial3d(material.clone()), RoadMesh))
+            .id();
+        Some(entity)
     }
-
-    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
-        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
-        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
-        .with_inserted_indices(Indices::U32(indices));
-
-    commands.spawn((
-        Mesh3d(meshes.add(mesh)),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: Color::WHITE,
-            perceptual_roughness: 0.9,
-            ..default()
-        })),
-        RoadMesh,
-    ));
 }
 
 /// Test if two line segments intersect in the XZ plane.
-/// Returns (t, u) parameters along segments A and B respectively.
-/// Excludes near-endpoint hits to avoid spurious splits at shared nodes.
+/// Returns (t, u) parameters along segments A and B respectively, each in
+/// `0..1` (start inclusive, end exclusive) so a crossing at the joint of two
+/// consecutive polyline pieces is only reported once.
 fn segment_intersection_xz(a1: Vec3, a2: Vec3, b1: Vec3, b2: Vec3) -> Option<(f32, f32)> {
     let d1x = a2.x - a1.x;
     let d1z = a2.z - a1.z;
@@ -425,16 +1054,29 @@ fn segment_intersection_xz(a1: Vec3, a2: Vec3, b1: Vec3, b2: Vec3) -> Option<(f3
     let t = (dx * d2z - dz * d2x) / cross;
     let u = (dx * d1z - dz * d1x) / cross;
 
-    // Exclude hits very close to endpoints to avoid double-splits at shared nodes
-    let eps = 0.01;
-    if t > eps && t < (1.0 - eps) && u > eps && u < (1.0 - eps) {
+    if (0.0..1.0).contains(&t) && (0.0..1.0).contains(&u) {
         Some((t, u))
     } else {
         None
     }
 }
 
+/// Crossings between two sampled polylines in the XZ plane, as the position
+/// along `a` (piece index plus fraction) and the crossing point on `b`.
+fn polyline_crossings_xz(a: &[Vec3], b: &[Vec3]) -> Vec<(f32, Vec3)> {
+    let mut crossings = Vec::new();
+    for (i, pa) in a.windows(2).enumerate() {
+        for pb in b.windows(2) {
+            if let Some((t, u)) = segment_intersection_xz(pa[0], pa[1], pb[0], pb[1]) {
+                crossings.push((i as f32 + t, pb[0].lerp(pb[1], u)));
+            }
+        }
+    }
+    crossings
+}
+
 /// The currently active player tool.
+#[cfg(feature = "gui")]
 #[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ActiveTool {
     #[default]
@@ -442,21 +1084,33 @@ pub enum ActiveTool {
     Road,
     Zone,
     Building,
+    Terraform,
+    Survey,
+    Upgrade,
+    Bulldoze,
 }
 
+/// Distance (world units) from a road centerline within which the cursor
+/// picks that road for the bulldoze and upgrade tools.
+#[cfg(feature = "gui")]
+pub const SEGMENT_PICK_RADIUS: f32 = 2.5;
+
 /// Distance (world units) within which a click snaps to an existing node.
 const SNAP_RADIUS: f32 = 3.0;
 
 /// Minimum distance between consecutive placed points to prevent micro-roads from misclicks.
+#[cfg(feature = "gui")]
 const MIN_SEGMENT_LENGTH: f32 = 3.0;
 
 /// Tracks in-progress road placement (points placed so far).
+#[cfg(feature = "gui")]
 #[derive(Resource, Default)]
 pub struct RoadPlacementState {
     pub points: Vec<Vec3>,
 }
 
 /// Toggle road placement tool with R key.
+#[cfg(feature = "gui")]
 pub fn toggle_road_tool(
     keys: Res<ButtonInput<KeyCode>>,
     egui_input: Res<EguiWantsInput>,
@@ -476,19 +1130,103 @@ pub fn toggle_road_tool(
     }
 }
 
-/// Place road control points on the terrain via mouse click + raycast.
+/// Crossings closer than this (world units) to an existing node reuse the node
+/// instead of splitting a segment next to it.
+const JUNCTION_MERGE_DISTANCE: f32 = 1.0;
+
+/// Add a road through the placed points to the network.
+///
+/// The endpoints snap to existing nodes within `SNAP_RADIUS`, or else onto the
+/// middle of a segment, which is split there to form a T-junction. The new road's
+/// spline is intersected with every existing segment's spline; each crossed
+/// segment is split at the true crossing (keeping its curve), and the new road
+/// becomes a chain of segments through the crossing nodes, each keeping the
+/// placed points that fall between its ends as control points.
+fn commit_road(road_network: &mut RoadNetwork, mut points: Vec<Vec3>) {
+    let last = points.len() - 1;
+    let start_node = road_network.attach(points[0]);
+    let end_node = road_network.attach(points[last]);
+    points[0] = road_network.node(start_node).unwrap().position;
+    points[last] = road_network.node(end_node).unwrap().position;
+
+    // Crossings with existing roads, as (position along the new curve, segment, point)
+    let curve = sample_catmull_rom(&points, MESH_SAMPLES_PER_SEGMENT);
+    let near = |a: Vec3, b: Vec3| Vec2::new(a.x, a.z).distance(Vec2::new(b.x, b.z)) < JUNCTION_MERGE_DISTANCE;
+    let mut crossings: Vec<(f32, SegmentId, Vec3)> = Vec::new();
+    let (min, max) = xz_bounds(curve.iter().copied(), 0.0);
+    for seg_id in road_network.segments_in_aabb(min, max) {
+        let Some(existing) = road_network.segment_curve(seg_id) else { continue };
+        for (along, point) in polyline_crossings_xz(&curve, existing) {
+            if !near(point, points[0]) && !near(point, points[last]) {
+                crossings.push((along, seg_id, point));
+            }
+        }
+    }
+    crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Chain of nodes along the new road, each with the index of the first
+    // placed point after it
+    let mut chain: Vec<(NodeId, usize)> = vec![(start_node, 1)];
+    let mut replaced: HashMap<SegmentId, [SegmentId; 2]> = HashMap::new();
+    for (along, mut seg_id, point) in crossings {
+        // An earlier crossing may have split this segment already
+        while let Some(halves) = replaced.get(&seg_id) {
+            let distance = |id: SegmentId| {
+                road_network
+                    .segment_curve(id)
+                    .and_then(|c| closest_on_polyline(c, Vec2::new(point.x, point.z)))
+                    .map_or(f32::INFINITY, |(_, _, d)| d)
+            };
+            seg_id = if distance(halves[0]) <= distance(halves[1]) { halves[0] } else { halves[1] };
+        }
+        let Some(segment) = road_network.segment(seg_id) else { continue };
+
+        let existing_node = segment
+            .nodes
+            .into_iter()
+            .find(|&id| road_network.node(id).is_some_and(|node| near(node.position, point)));
+        let node = match existing_node {
+            Some(node) => node,
+            None => {
+                let node = road_network.split_segment_at(seg_id, point);
+                let halves = &road_network.node(node).unwrap().segments;
+                replaced.insert(seg_id, [halves[0], halves[1]]);
+                node
+            }
+        };
+
+        let span = along as usize / MESH_SAMPLES_PER_SEGMENT;
+        if chain.last().is_some_and(|&(last_node, _)| last_node == node) {
+            continue;
+        }
+        chain.push((node, span + 1));
+    }
+    chain.push((end_node, last));
+
+    for pair in chain.windows(2) {
+        let ((from, first), (to, next_first)) = (pair[0], pair[1]);
+        if from == to {
+            continue;
+        }
+        let control_points = points[first..next_first.max(first)].to_vec();
+        road_network.add_segment(from, to, control_points, RoadType::Dirt, 2.0);
+    }
+}
+
+/// Place road control points on the terrain via mouse click + heightmap raycast.
 ///
 /// - Left click: place a point on the terrain
 /// - Enter: confirm the road (creates nodes and a segment in the RoadNetwork)
 /// - Escape: cancel placement
+#[cfg(feature = "gui")]
 pub fn road_placement_input(
-    mut ray_cast: MeshRayCast,
     camera_query: Query<(&Camera, &GlobalTransform)>,
     window: Query<&Window>,
     mouse_buttons: Res<ButtonInput<MouseButton>>,
     keys: Res<ButtonInput<KeyCode>>,
     egui_input: Res<EguiWantsInput>,
-    terrain_query: Query<(), With<TerrainMesh>>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
     mut placement: ResMut<RoadPlacementState>,
     mut road_network: ResMut<RoadNetwork>,
     mut notifications: ResMut<Notifications>,
@@ -507,57 +1245,7 @@ pub fn road_placement_input(
     // Confirm road with Enter (need at least 2 points)
     if keys.just_pressed(KeyCode::Enter) && placement.points.len() >= 2 {
         let points = std::mem::take(&mut placement.points);
-        let start_pos = points[0];
-        let end_pos = *points.last().unwrap();
-
-        let start_node = road_network.nearest_node(start_pos, SNAP_RADIUS)
-            .unwrap_or_else(|| road_network.add_node(start_pos));
-        let end_node = road_network.nearest_node(end_pos, SNAP_RADIUS)
-            .unwrap_or_else(|| road_network.add_node(end_pos));
-
-        let start_world = road_network.node(start_node).unwrap().position;
-        let end_world = road_network.node(end_node).unwrap().position;
-
-        // Find intersections between the new road line and existing segments
-        let mut intersections: Vec<(SegmentId, Vec3)> = Vec::new();
-        for (&seg_id, segment) in road_network.segments() {
-            let a = road_network.node(segment.nodes[0]).unwrap().position;
-            let b = road_network.node(segment.nodes[1]).unwrap().position;
-            if let Some((t, _)) = segment_intersection_xz(start_world, end_world, a, b) {
-                let point = start_world.lerp(end_world, t);
-                intersections.push((seg_id, point));
-            }
-        }
-
-        if intersections.is_empty() {
-            // No crossings — create single segment with control points
-            let control_points: Vec<Vec3> = if points.len() > 2 {
-                points[1..points.len() - 1].to_vec()
-            } else {
-                Vec::new()
-            };
-            road_network.add_segment(start_node, end_node, control_points, RoadType::Dirt, 2.0);
-        } else {
-            // Sort intersections by distance along new road from start
-            intersections.sort_by(|a, b| {
-                let da = a.1.distance_squared(start_world);
-                let db = b.1.distance_squared(start_world);
-                da.partial_cmp(&db).unwrap()
-            });
-
-            // Split each crossed segment and collect intersection node IDs
-            let mut chain: Vec<NodeId> = vec![start_node];
-            for (seg_id, point) in intersections {
-                let int_node = road_network.split_segment_at(seg_id, point);
-                chain.push(int_node);
-            }
-            chain.push(end_node);
-
-            // Create new road as chain of segments through intersection nodes
-            for pair in chain.windows(2) {
-                road_network.add_segment(pair[0], pair[1], Vec::new(), RoadType::Dirt, 2.0);
-            }
-        }
+        commit_road(&mut road_network, points);
         notifications.push("Road built", 3.0);
         return;
     }
@@ -583,18 +1271,12 @@ pub fn road_placement_input(
         return;
     };
 
-    let filter = |entity: Entity| terrain_query.contains(entity);
-    let settings = MeshRayCastSettings::default()
-        .with_filter(&filter);
-
-    let hits = ray_cast.cast_ray(ray, &settings);
-    if let Some((_, hit)) = hits.first() {
-        // Snap to nearby existing node if one exists
-        let point = if let Some(node_id) = road_network.nearest_node(hit.point, SNAP_RADIUS) {
-            road_network.node(node_id).unwrap().position
-        } else {
-            hit.point
-        };
+    if let Some(hit) = heightmap.raycast(ray, config.map_size) {
+        // Snap to a nearby existing node, or onto a nearby segment
+        let point = road_network
+            .snap(hit, SNAP_RADIUS)
+            .and_then(|snap| snap.position(&road_network))
+            .unwrap_or(hit);
         // Reject if too close to the last placed point
         if let Some(&last) = placement.points.last() {
             if point.distance(last) < MIN_SEGMENT_LENGTH {
@@ -607,9 +1289,11 @@ pub fn road_placement_input(
 
 /// Draw gizmo preview of the road being placed (yellow)
 /// and debug visualization of all committed roads in the network (white nodes, orange segments).
+#[cfg(feature = "gui")]
 pub fn draw_road_debug(
     placement: Res<RoadPlacementState>,
     active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
     road_network: Res<RoadNetwork>,
     debug_visible: Res<crate::ui::DebugVisible>,
     mut gizmos: Gizmos,
@@ -638,8 +1322,34 @@ pub fn draw_road_debug(
         }
     }
 
+    if *active_tool != ActiveTool::Road {
+        return;
+    }
+
+    // --- Snap target under the cursor: ring on a node, junction cross on a segment ---
+    if let Some(cursor) = cursor_pos.position
+        && let Some(snap) = road_network.snap(cursor, SNAP_RADIUS)
+        && let Some(position) = snap.position(&road_network)
+    {
+        let snap_color = Color::srgb(0.2, 0.9, 1.0);
+        let ring = Isometry3d::new(position + Vec3::Y * 0.3, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2));
+        gizmos.circle(ring, 1.2, snap_color);
+        if let RoadSnap::Segment { segment, .. } = snap
+            && let Some(seg) = road_network.segment(segment)
+        {
+            // Stub of the new branch, perpendicular to the road at the junction
+            let curve = road_network.segment_curve(segment).unwrap_or_default();
+            let piece = closest_on_polyline(curve, Vec2::new(position.x, position.z)).map_or(0, |(i, _, _)| i);
+            if piece + 1 < curve.len() {
+                let forward = (curve[piece + 1] - curve[piece]).with_y(0.0).normalize_or_zero();
+                let side = Vec3::new(-forward.z, 0.0, forward.x) * (seg.width / 2.0 + 1.5);
+                gizmos.line(position - side + Vec3::Y * 0.3, position + side + Vec3::Y * 0.3, snap_color);
+            }
+        }
+    }
+
     // --- In-progress placement preview (yellow curve, always visible) ---
-    if *active_tool != ActiveTool::Road || placement.points.is_empty() {
+    if placement.points.is_empty() {
         return;
     }
 
@@ -655,3 +1365,187 @@ pub fn draw_road_debug(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Nodes with exactly `degree` connected segments.
+    fn nodes_with_degree(network: &RoadNetwork, degree: usize) -> Vec<NodeId> {
+        network
+            .nodes()
+            .iter()
+            .filter(|(_, node)| node.segments.len() == degree)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// The segment joining two nodes, if any.
+    fn segment_between(network: &RoadNetwork, a: NodeId, b: NodeId) -> Option<&RoadSegment> {
+        network.segments().values().find(|s| s.nodes == [a, b] || s.nodes == [b, a])
+    }
+
+    fn distance_to_curve(curve: &[Vec3], point: Vec3) -> f32 {
+        closest_on_polyline(curve, Vec2::new(point.x, point.z)).unwrap().2
+    }
+
+    #[test]
+    fn curved_crossing_splits_at_spline() {
+        let mut network = RoadNetwork::default();
+        let south = network.add_node(Vec3::new(0.0, 0.0, -40.0));
+        let north = network.add_node(Vec3::new(0.0, 0.0, 40.0));
+        let bend = vec![Vec3::new(10.0, 0.0, -15.0), Vec3::new(10.0, 0.0, 15.0)];
+        let existing = network.add_segment(south, north, bend.clone(), RoadType::Dirt, 2.0);
+        let existing_curve = network.segment_curve(existing).unwrap().to_vec();
+
+        let points = vec![Vec3::new(-30.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 6.0), Vec3::new(30.0, 0.0, 0.0)];
+        let new_curve = sample_catmull_rom(&points, MESH_SAMPLES_PER_SEGMENT);
+        commit_road(&mut network, points.clone());
+
+        assert_eq!(network.segments().len(), 4);
+        let crossing = nodes_with_degree(&network, 4);
+        assert_eq!(crossing.len(), 1);
+        let position = network.node(crossing[0]).unwrap().position;
+
+        // On both splines, well away from the straight chord at x = 0
+        assert!(distance_to_curve(&existing_curve, position) < 0.1);
+        assert!(distance_to_curve(&new_curve, position) < 0.1);
+        assert!(position.x > 8.0);
+
+        // Each half of the crossed road keeps the control point on its side
+        let first = segment_between(&network, south, crossing[0]).unwrap();
+        let second = segment_between(&network, crossing[0], north).unwrap();
+        assert_eq!(first.control_points, vec![bend[0]]);
+        assert_eq!(second.control_points, vec![bend[1]]);
+
+        // The new road's bend stays on the half it belongs to
+        let start = network.nearest_node(points[0], 0.1).unwrap();
+        let end = network.nearest_node(points[2], 0.1).unwrap();
+        assert_eq!(segment_between(&network, start, crossing[0]).unwrap().control_points, vec![points[1]]);
+        assert!(segment_between(&network, crossing[0], end).unwrap().control_points.is_empty());
+    }
+
+    #[test]
+    fn crossing_two_roads_makes_chain() {
+        let mut network = RoadNetwork::default();
+        for x in [-10.0, 10.0] {
+            let a = network.add_node(Vec3::new(x, 0.0, -20.0));
+            let b = network.add_node(Vec3::new(x, 0.0, 20.0));
+            network.add_segment(a, b, Vec::new(), RoadType::Dirt, 2.0);
+        }
+
+        let points = vec![
+            Vec3::new(-30.0, 0.0, 0.0),
+            Vec3::new(-20.0, 0.0, 2.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(20.0, 0.0, 2.0),
+            Vec3::new(30.0, 0.0, 0.0),
+        ];
+        commit_road(&mut network, points.clone());
+
+        // Two crossed roads split in half, plus three new pieces
+        assert_eq!(network.segments().len(), 7);
+        let mut crossings = nodes_with_degree(&network, 4);
+        assert_eq!(crossings.len(), 2);
+        crossings.sort_by(|a, b| {
+            let x = |id: &NodeId| network.node(*id).unwrap().position.x;
+            x(a).total_cmp(&x(b))
+        });
+
+        let start = network.nearest_node(points[0], 0.1).unwrap();
+        let end = network.nearest_node(points[4], 0.1).unwrap();
+        let chain = [start, crossings[0], crossings[1], end];
+        for (i, pair) in chain.windows(2).enumerate() {
+            let segment = segment_between(&network, pair[0], pair[1]).unwrap();
+            assert_eq!(segment.control_points, vec![points[i + 1]]);
+        }
+    }
+
+    #[test]
+    fn crossing_near_node_reuses_it() {
+        let mut network = RoadNetwork::default();
+        let south = network.add_node(Vec3::new(0.0, 0.0, -20.0));
+        let middle = network.add_node(Vec3::ZERO);
+        let north = network.add_node(Vec3::new(0.0, 0.0, 20.0));
+        network.add_segment(south, middle, Vec::new(), RoadType::Dirt, 2.0);
+        network.add_segment(middle, north, Vec::new(), RoadType::Dirt, 2.0);
+
+        let offset = JUNCTION_MERGE_DISTANCE / 2.0;
+        commit_road(&mut network, vec![Vec3::new(-20.0, 0.0, offset), Vec3::new(20.0, 0.0, offset)]);
+
+        assert_eq!(network.nodes().len(), 5);
+        assert_eq!(network.segments().len(), 4);
+        assert_eq!(nodes_with_degree(&network, 4), vec![middle]);
+        assert_eq!(network.node(middle).unwrap().position, Vec3::ZERO);
+    }
+
+    #[test]
+    fn endpoint_on_segment_makes_t_junction() {
+        let mut network = RoadNetwork::default();
+        let south = network.add_node(Vec3::new(0.0, 0.0, -20.0));
+        let north = network.add_node(Vec3::new(0.0, 0.0, 20.0));
+        network.add_segment(south, north, Vec::new(), RoadType::Dirt, 2.0);
+
+        // Ends short of the road, but within snapping range of its middle
+        commit_road(&mut network, vec![Vec3::new(-20.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)]);
+
+        assert_eq!(network.nodes().len(), 4);
+        assert_eq!(network.segments().len(), 3);
+        let junction = nodes_with_degree(&network, 3);
+        assert_eq!(junction.len(), 1);
+        assert!(network.node(junction[0]).unwrap().position.distance(Vec3::ZERO) < 0.01);
+        assert_eq!(network.node(south).unwrap().segments.len(), 1);
+        assert_eq!(network.node(north).unwrap().segments.len(), 1);
+    }
+
+    #[test]
+    fn split_is_recorded_with_length_shares() {
+        let mut network = RoadNetwork::default();
+        let south = network.add_node(Vec3::new(0.0, 0.0, -10.0));
+        let north = network.add_node(Vec3::new(0.0, 0.0, 30.0));
+        let road = network.add_segment(south, north, Vec::new(), RoadType::Dirt, 2.0);
+
+        let middle = network.split_segment_at(road, Vec3::ZERO);
+
+        let splits = network.take_splits();
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].original, road);
+        let [first, second] = splits[0].halves;
+        assert_eq!(network.segment(first).unwrap().nodes, [south, middle]);
+        assert_eq!(network.segment(second).unwrap().nodes, [middle, north]);
+        assert!((splits[0].shares[0] - 0.25).abs() < 1e-4);
+        assert!((splits[0].shares[1] - 0.75).abs() < 1e-4);
+        assert!(network.take_splits().is_empty());
+    }
+
+    #[test]
+    fn drape_queues_only_moved_segments() {
+        let mut network = RoadNetwork::default();
+        let a = network.add_node(Vec3::new(-20.0, 0.0, 0.0));
+        let b = network.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let c = network.add_node(Vec3::new(0.0, 0.0, 30.0));
+        let ab = network.add_segment(a, b, vec![Vec3::new(-10.0, 0.0, 2.0)], RoadType::Dirt, 2.0);
+        let bc = network.add_segment(b, c, Vec::new(), RoadType::Dirt, 2.0);
+        network.take_changes();
+
+        // Raise the ground under `ab`'s control point only. `bc`'s bounds reach
+        // the rectangle, but none of its points are inside it
+        let heightmap = Heightmap {
+            heights: vec![1.0; 65 * 65],
+            resolution: 65,
+        };
+        let moved = network.drape_region(&heightmap, 130.0, Vec2::new(-12.0, 0.0), Vec2::new(-1.5, 4.0));
+
+        assert!(network.segments_in_aabb(Vec2::new(-12.0, 0.0), Vec2::new(-1.5, 4.0)).contains(&bc));
+        assert!(moved);
+        assert_eq!(network.segment(ab).unwrap().control_points[0].y, 1.0);
+        let (segments, nodes) = network.take_changes();
+        assert_eq!(segments, HashSet::from([ab]));
+        assert!(!segments.contains(&bc));
+        assert_eq!(nodes, HashSet::from([a, b]));
+
+        // Draping again changes nothing
+        assert!(!network.drape_region(&heightmap, 130.0, Vec2::new(-12.0, 0.0), Vec2::new(-1.5, 4.0)));
+        assert!(network.take_changes().0.is_empty());
+    }
+}
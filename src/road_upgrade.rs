@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use std::collections::HashMap;
+
+use crate::road::{
+    ActiveTool, RoadNetwork, RoadPlacementState, RoadType, SegmentId, SEGMENT_PICK_RADIUS,
+};
+use crate::notifications::Notifications;
+use crate::ui::{CityBudget, CursorWorldPosition, GameTime};
+
+// ---------------------------------------------------------------------------
+// Resources
+// ---------------------------------------------------------------------------
+
+/// A surface upgrade under construction. The segment keeps its current
+/// surface (and speed) until the work is done.
+pub struct PendingUpgrade {
+    pub target: RoadType,
+    /// Funds paid up front, refunded if the segment is removed first.
+    pub cost: f32,
+    /// Total construction time in game seconds.
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+impl PendingUpgrade {
+    /// Fraction of the construction completed (0..1).
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Road upgrades under construction, keyed by segment.
+#[derive(Resource, Default)]
+pub struct RoadUpgrades {
+    pub pending: HashMap<SegmentId, PendingUpgrade>,
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Toggle the road upgrade tool with U key.
+pub fn toggle_upgrade_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut active_tool: ResMut<ActiveTool>,
+    mut placement: ResMut<RoadPlacementState>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyU) {
+        if *active_tool == ActiveTool::Upgrade {
+            *active_tool = ActiveTool::None;
+        } else {
+            *active_tool = ActiveTool::Upgrade;
+        }
+        placement.points.clear();
+    }
+}
+
+/// Start upgrading the hovered road segment to its next surface on left
+/// click, paying `RoadType::cost_per_unit` of the new surface per unit of
+/// segment length up front.
+#[allow(clippy::too_many_arguments)]
+pub fn upgrade_input(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    egui_input: Res<EguiWantsInput>,
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    road_network: Res<RoadNetwork>,
+    mut upgrades: ResMut<RoadUpgrades>,
+    mut budget: ResMut<CityBudget>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if *active_tool != ActiveTool::Upgrade
+        || !mouse_buttons.just_pressed(MouseButton::Left)
+        || egui_input.wants_any_pointer_input()
+    {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+    let Some((segment_id, _)) =
+        road_network.nearest_segment_point(Vec2::new(pos.x, pos.z), SEGMENT_PICK_RADIUS)
+    else {
+        return;
+    };
+    let (Some(segment), Some(length)) = (road_network.segment(segment_id), road_network.segment_length(segment_id))
+    else {
+        return;
+    };
+
+    if upgrades.pending.contains_key(&segment_id) {
+        notifications.push("Upgrade already under way", 3.0);
+        return;
+    }
+    let Some(target) = segment.road_type.upgrade() else {
+        notifications.push(format!("Road is already {}", segment.road_type.label()), 3.0);
+        return;
+    };
+
+    let cost = length * target.cost_per_unit();
+    if budget.funds < cost {
+        notifications.push(format!("Not enough funds: ${:.0} needed", cost), 3.0);
+        return;
+    }
+    budget.funds -= cost;
+
+    let duration = length * target.build_time_per_unit();
+    upgrades.pending.insert(segment_id, PendingUpgrade {
+        target,
+        cost,
+        duration,
+        elapsed: 0.0,
+    });
+    notifications.push(
+        format!("Upgrading to {}: ${:.0}, {:.0} s", target.label(), cost, duration),
+        3.0,
+    );
+}
+
+/// Advance upgrade construction with game time, switching each segment to its
+/// new surface once done. An upgrade on a segment that was split carries over
+/// to both halves, dividing its cost, duration and elapsed time by length so
+/// each keeps the same progress. Upgrades whose segment was removed are
+/// cancelled and refunded.
+pub fn advance_road_upgrades(
+    time: Res<Time>,
+    game_time: Res<GameTime>,
+    mut road_network: ResMut<RoadNetwork>,
+    mut upgrades: ResMut<RoadUpgrades>,
+    mut budget: ResMut<CityBudget>,
+    mut notifications: ResMut<Notifications>,
+) {
+    // Drain the splits every frame so they can't pile up while nothing is pending
+    let splits = road_network.bypass_change_detection().take_splits();
+    if upgrades.pending.is_empty() {
+        return;
+    }
+    for split in splits {
+        let Some(upgrade) = upgrades.pending.remove(&split.original) else {
+            continue;
+        };
+        for (half, share) in split.halves.into_iter().zip(split.shares) {
+            upgrades.pending.insert(half, PendingUpgrade {
+                target: upgrade.target,
+                cost: upgrade.cost * share,
+                duration: upgrade.duration * share,
+                elapsed: upgrade.elapsed * share,
+            });
+        }
+    }
+
+    let dt = time.delta_secs() * game_time.speed.multiplier();
+
+    let mut finished: Vec<(SegmentId, RoadType)> = Vec::new();
+    upgrades.pending.retain(|&segment_id, upgrade| {
+        if road_network.segment(segment_id).is_none() {
+            budget.funds += upgrade.cost;
+            notifications.push(format!("Road upgrade cancelled, ${:.0} refunded", upgrade.cost), 3.0);
+            return false;
+        }
+        upgrade.elapsed += dt;
+        if upgrade.elapsed >= upgrade.duration {
+            finished.push((segment_id, upgrade.target));
+            return false;
+        }
+        true
+    });
+
+    // Only touch the network on completion, so meshes rebuild once per upgrade
+    for (segment_id, target) in finished {
+        road_network.set_road_type(segment_id, target);
+        notifications.push(format!("Road upgraded to {}", target.label()), 3.0);
+    }
+}
+
+/// Draw upgrades under construction (the finished share of each segment in
+/// the new surface's color) and, with the upgrade tool active, highlight the
+/// hovered segment.
+pub fn draw_road_upgrades(
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    road_network: Res<RoadNetwork>,
+    upgrades: Res<RoadUpgrades>,
+    mut gizmos: Gizmos,
+) {
+    let lift = Vec3::Y * 0.4;
+
+    for (&segment_id, upgrade) in &upgrades.pending {
+        let Some(curve) = road_network.segment_curve(segment_id) else {
+            continue;
+        };
+        let [r, g, b, _] = upgrade.target.color();
+        let done = Color::srgb(r, g, b);
+        let todo = Color::srgb(1.0, 0.75, 0.2);
+        let split = ((curve.len() - 1) as f32 * upgrade.progress()).round() as usize;
+        for (i, pair) in curve.windows(2).enumerate() {
+            let color = if i < split { done } else { todo };
+            gizmos.line(pair[0] + lift, pair[1] + lift, color);
+        }
+    }
+
+    if *active_tool != ActiveTool::Upgrade {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+    let Some((segment_id, _)) =
+        road_network.nearest_segment_point(Vec2::new(pos.x, pos.z), SEGMENT_PICK_RADIUS)
+    else {
+        return;
+    };
+    if upgrades.pending.contains_key(&segment_id) {
+        return;
+    }
+    let Some(curve) = road_network.segment_curve(segment_id) else {
+        return;
+    };
+    let color = Color::srgb(0.3, 0.9, 0.4);
+    for pair in curve.windows(2) {
+        gizmos.line(pair[0] + lift, pair[1] + lift, color);
+    }
+}
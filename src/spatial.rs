@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Uniform grid over the XZ plane that buckets items by their bounding boxes,
+/// for radius and AABB queries without scanning everything.
+///
+/// An item is stored in every cell its box overlaps. Queries return each
+/// candidate once, in no particular order; callers still run their exact test
+/// on the results.
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<T>>,
+    /// Cell range (min, max inclusive) each item occupies, for removal.
+    extents: HashMap<T, (IVec2, IVec2)>,
+}
+
+impl<T: Copy + Eq + Hash> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            extents: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Add an item covering the (x, z) box `min..max`, replacing any box it
+    /// had before.
+    pub fn insert(&mut self, item: T, min: Vec2, max: Vec2) {
+        self.remove(item);
+        let (lo, hi) = (self.cell(min), self.cell(max));
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(item);
+            }
+        }
+        self.extents.insert(item, (lo, hi));
+    }
+
+    /// Remove an item. Does nothing if it is not in the grid.
+    pub fn remove(&mut self, item: T) {
+        let Some((lo, hi)) = self.extents.remove(&item) else {
+            return;
+        };
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                let key = IVec2::new(x, y);
+                if let Some(bucket) = self.cells.get_mut(&key) {
+                    bucket.retain(|&other| other != item);
+                    if bucket.is_empty() {
+                        self.cells.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Items whose boxes may overlap the (x, z) box `min..max`.
+    pub fn query_aabb(&self, min: Vec2, max: Vec2) -> Vec<T> {
+        let (lo, hi) = (self.cell(min), self.cell(max));
+        let mut found = HashSet::new();
+        for y in lo.y..=hi.y {
+            for x in lo.x..=hi.x {
+                if let Some(bucket) = self.cells.get(&IVec2::new(x, y)) {
+                    found.extend(bucket.iter().copied());
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    /// Items whose boxes may come within `radius` of an (x, z) point.
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> Vec<T> {
+        self.query_aabb(center - Vec2::splat(radius), center + Vec2::splat(radius))
+    }
+}
+
+/// Bounding box of a set of points in the XZ plane, grown by `margin`.
+pub fn xz_bounds(points: impl IntoIterator<Item = Vec3>, margin: f32) -> (Vec2, Vec2) {
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+    for p in points {
+        min = min.min(Vec2::new(p.x, p.z));
+        max = max.max(Vec2::new(p.x, p.z));
+    }
+    (min - Vec2::splat(margin), max + Vec2::splat(margin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Query results sorted, since the grid returns them in no particular order.
+    fn sorted(mut items: Vec<u32>) -> Vec<u32> {
+        items.sort_unstable();
+        items
+    }
+
+    #[test]
+    fn insert_and_query_point() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, Vec2::new(5.0, 5.0), Vec2::new(5.0, 5.0));
+        grid.insert(2, Vec2::new(55.0, 5.0), Vec2::new(55.0, 5.0));
+
+        assert_eq!(grid.query_radius(Vec2::new(4.0, 4.0), 1.0), vec![1]);
+        assert_eq!(grid.query_radius(Vec2::new(55.0, 5.0), 1.0), vec![2]);
+        assert!(grid.query_radius(Vec2::new(30.0, 30.0), 2.0).is_empty());
+    }
+
+    #[test]
+    fn reinsert_moves_item() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, Vec2::new(5.0, 5.0), Vec2::new(5.0, 5.0));
+        grid.insert(1, Vec2::new(45.0, 45.0), Vec2::new(45.0, 45.0));
+
+        assert!(grid.query_radius(Vec2::new(5.0, 5.0), 1.0).is_empty());
+        assert_eq!(grid.query_radius(Vec2::new(45.0, 45.0), 1.0), vec![1]);
+        // Each item comes back once even when it spans several cells
+        grid.insert(1, Vec2::new(0.0, 0.0), Vec2::new(35.0, 35.0));
+        assert_eq!(grid.query_aabb(Vec2::new(-5.0, -5.0), Vec2::new(50.0, 50.0)), vec![1]);
+    }
+
+    #[test]
+    fn remove_clears_every_cell() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, Vec2::new(0.0, 0.0), Vec2::new(25.0, 25.0));
+        grid.insert(2, Vec2::new(15.0, 15.0), Vec2::new(15.0, 15.0));
+        grid.remove(1);
+        grid.remove(3);
+
+        assert!(grid.query_radius(Vec2::new(2.0, 2.0), 1.0).is_empty());
+        assert!(grid.query_radius(Vec2::new(24.0, 24.0), 1.0).is_empty());
+        assert_eq!(grid.query_radius(Vec2::new(15.0, 15.0), 1.0), vec![2]);
+    }
+
+    #[test]
+    fn queries_cross_cell_borders() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, Vec2::new(9.5, 9.5), Vec2::new(9.5, 9.5));
+        grid.insert(2, Vec2::new(10.5, 10.5), Vec2::new(10.5, 10.5));
+        grid.insert(3, Vec2::new(10.5, 0.5), Vec2::new(12.0, 9.0));
+
+        assert_eq!(sorted(grid.query_radius(Vec2::new(10.0, 10.0), 1.0)), vec![1, 2, 3]);
+        assert_eq!(sorted(grid.query_aabb(Vec2::new(9.0, 9.0), Vec2::new(9.9, 9.9))), vec![1]);
+        assert_eq!(sorted(grid.query_aabb(Vec2::new(10.1, 0.0), Vec2::new(19.0, 19.0))), vec![2, 3]);
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, Vec2::new(-0.5, -0.5), Vec2::new(-0.5, -0.5));
+        grid.insert(2, Vec2::new(0.5, 0.5), Vec2::new(0.5, 0.5));
+        grid.insert(3, Vec2::new(-35.0, -25.0), Vec2::new(-15.0, 5.0));
+
+        // -0.5 floors into cell -1, not cell 0 alongside 0.5
+        assert_eq!(grid.query_aabb(Vec2::new(-9.0, -9.0), Vec2::new(-1.0, -1.0)), vec![1]);
+        assert_eq!(sorted(grid.query_radius(Vec2::ZERO, 1.0)), vec![1, 2]);
+        assert_eq!(grid.query_radius(Vec2::new(-30.0, -20.0), 1.0), vec![3]);
+        assert!(grid.query_radius(Vec2::new(-45.0, -45.0), 1.0).is_empty());
+    }
+}
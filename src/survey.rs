@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::resources::{ResourceMap, ResourceType};
+use crate::road::{ActiveTool, RoadPlacementState};
+use crate::terrain::{build_chunk_mesh, BiomeMap, Heightmap, TerrainChunk, TerrainConfig};
+use crate::notifications::Notifications;
+use crate::ui::CursorWorldPosition;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Radius (world units) prospected by one survey.
+const SURVEY_RADIUS: f32 = 30.0;
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Toggle the survey tool with V key.
+pub fn toggle_survey_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut active_tool: ResMut<ActiveTool>,
+    mut placement: ResMut<RoadPlacementState>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyV) {
+        if *active_tool == ActiveTool::Survey {
+            *active_tool = ActiveTool::None;
+        } else {
+            *active_tool = ActiveTool::Survey;
+        }
+        placement.points.clear();
+    }
+}
+
+/// Survey the ground around the cursor on left click, revealing hidden
+/// subsurface resources (Coal, Clay, Stone) within `SURVEY_RADIUS`.
+#[allow(clippy::too_many_arguments)]
+pub fn survey_input(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    egui_input: Res<EguiWantsInput>,
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    config: Res<TerrainConfig>,
+    mut resource_map: ResMut<ResourceMap>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if *active_tool != ActiveTool::Survey
+        || !mouse_buttons.just_pressed(MouseButton::Left)
+        || egui_input.wants_any_pointer_input()
+    {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+
+    let center = Vec2::new(pos.x, pos.z);
+    let Some(revealed) = resource_map.survey(center, SURVEY_RADIUS, config.map_size) else {
+        notifications.push("Survey found nothing new", 3.0);
+        return;
+    };
+
+    let mut found: Vec<ResourceType> = Vec::new();
+    for row in revealed.min_row..=revealed.max_row {
+        for col in revealed.min_col..=revealed.max_col {
+            for entry in resource_map.known(row, col) {
+                if !entry.resource.is_surface() && !found.contains(&entry.resource) {
+                    found.push(entry.resource);
+                }
+            }
+        }
+    }
+    let names: Vec<&str> = found.iter().map(|resource| resource.label()).collect();
+    notifications.push(format!("Survey found {}", names.join(", ")), 4.0);
+}
+
+/// Draw the survey area draped on the terrain under the cursor.
+pub fn draw_survey_preview(
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
+    mut gizmos: Gizmos,
+) {
+    if *active_tool != ActiveTool::Survey {
+        return;
+    }
+    let Some(center) = cursor_pos.position else {
+        return;
+    };
+
+    let color = Color::srgb(1.0, 0.75, 0.2);
+    let sides = 48;
+    let rim: Vec<Vec3> = (0..=sides)
+        .map(|i| {
+            let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+            let x = center.x + angle.cos() * SURVEY_RADIUS;
+            let z = center.z + angle.sin() * SURVEY_RADIUS;
+            Vec3::new(x, heightmap.sample_world(x, z, config.map_size) + 0.3, z)
+        })
+        .collect();
+    for pair in rim.windows(2) {
+        gizmos.line(pair[0], pair[1], color);
+    }
+}
+
+/// Rebuild the terrain chunks over cells revealed by the survey tool or a
+/// working producer, so their resource tint shows. Runs after both.
+pub fn recolor_surveyed_chunks(
+    mut resource_map: ResMut<ResourceMap>,
+    config: Res<TerrainConfig>,
+    heightmap: Res<Heightmap>,
+    biome_map: Res<BiomeMap>,
+    chunks: Query<(&TerrainChunk, &Mesh3d)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !resource_map.has_revealed() {
+        return;
+    }
+    // Draining the queue isn't a resource change, so don't repaint the overlay
+    let Some(revealed) = resource_map.bypass_change_detection().take_revealed() else {
+        return;
+    };
+
+    for (chunk, mesh_handle) in &chunks {
+        if chunk.cells.intersects(&revealed)
+            && let Some(mesh) = meshes.get_mut(&mesh_handle.0)
+        {
+            *mesh = build_chunk_mesh(&heightmap, &biome_map, &resource_map, &config, chunk.cells, chunk.lod);
+        }
+    }
+}
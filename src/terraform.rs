@@ -0,0 +1,342 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::building::{drape_building, Building, Lot};
+use crate::climate::ClimateMap;
+use crate::resources::ResourceMap;
+use crate::road::{ActiveTool, RoadNetwork, RoadPlacementState};
+use crate::terrain::{
+    build_chunk_mesh, classify_biome, BiomeMap, CellRect, Heightmap, TerrainChunk, TerrainConfig,
+};
+use crate::ui::CursorWorldPosition;
+use crate::water::{RiverNetwork, WaterBodies};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const MIN_BRUSH_RADIUS: f32 = 2.0;
+const MAX_BRUSH_RADIUS: f32 = 40.0;
+const MIN_BRUSH_STRENGTH: f32 = 0.5;
+const MAX_BRUSH_STRENGTH: f32 = 20.0;
+
+/// Flatten and smooth blend toward their target at `strength * BLEND_RATE`
+/// per second at the brush center (so strength 5 covers half the gap in 1s).
+const BLEND_RATE: f32 = 0.1;
+
+// ---------------------------------------------------------------------------
+// Resources
+// ---------------------------------------------------------------------------
+
+/// What the terraform brush does to the ground under it.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerraformMode {
+    #[default]
+    Raise,
+    Lower,
+    /// Pull heights toward the height where the stroke started.
+    Flatten,
+    /// Pull heights toward the average of their neighbors.
+    Smooth,
+}
+
+impl TerraformMode {
+    pub const ALL: [TerraformMode; 4] = [
+        TerraformMode::Raise,
+        TerraformMode::Lower,
+        TerraformMode::Flatten,
+        TerraformMode::Smooth,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            TerraformMode::Raise => "Raise",
+            TerraformMode::Lower => "Lower",
+            TerraformMode::Flatten => "Flatten",
+            TerraformMode::Smooth => "Smooth",
+        }
+    }
+}
+
+/// Terraform brush settings, edited from the tool panel.
+///
+/// - `radius`: brush radius in world units. Falloff is smooth to zero at the edge.
+/// - `strength`: height change per second at the brush center for raise/lower,
+///   and blend speed for flatten/smooth.
+#[derive(Resource)]
+pub struct TerraformBrush {
+    pub mode: TerraformMode,
+    pub radius: f32,
+    pub strength: f32,
+    /// Height captured when a flatten stroke starts.
+    flatten_target: Option<f32>,
+}
+
+impl Default for TerraformBrush {
+    fn default() -> Self {
+        Self {
+            mode: TerraformMode::default(),
+            radius: 8.0,
+            strength: 5.0,
+            flatten_target: None,
+        }
+    }
+}
+
+/// Terrain changes not yet propagated to the rest of the world: heightmap
+/// cells changed by the terraform tool, whose biomes, terrain mesh, roads and
+/// buildings need updating.
+#[derive(Resource, Default)]
+pub struct TerrainEdits {
+    pub dirty: Option<CellRect>,
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Toggle the terraform tool with T key.
+pub fn toggle_terraform_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut active_tool: ResMut<ActiveTool>,
+    mut placement: ResMut<RoadPlacementState>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyT) {
+        if *active_tool == ActiveTool::Terraform {
+            *active_tool = ActiveTool::None;
+        } else {
+            *active_tool = ActiveTool::Terraform;
+        }
+        placement.points.clear();
+    }
+}
+
+/// Apply the terraform brush to the heightmap while the left mouse button is
+/// held, and record the touched cells in `TerrainEdits`.
+#[allow(clippy::too_many_arguments)]
+pub fn terraform_input(
+    time: Res<Time>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    egui_input: Res<EguiWantsInput>,
+    active_tool: Res<ActiveTool>,
+    cursor_pos: Res<CursorWorldPosition>,
+    config: Res<TerrainConfig>,
+    mut brush: ResMut<TerraformBrush>,
+    mut heightmap: ResMut<Heightmap>,
+    mut edits: ResMut<TerrainEdits>,
+) {
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        brush.flatten_target = None;
+        return;
+    }
+    if *active_tool != ActiveTool::Terraform || egui_input.wants_any_pointer_input() {
+        return;
+    }
+    let Some(pos) = cursor_pos.position else {
+        return;
+    };
+
+    if brush.flatten_target.is_none() {
+        brush.flatten_target = Some(heightmap.sample_world(pos.x, pos.z, config.map_size));
+    }
+
+    let res = heightmap.resolution;
+    let cell_size = config.map_size / res as f32;
+    let half = config.map_size / 2.0;
+    let max_index = (res - 1) as f32;
+
+    let center_col = (pos.x + half) / cell_size;
+    let center_row = (pos.z + half) / cell_size;
+    let radius_cells = brush.radius / cell_size;
+
+    let rect = CellRect {
+        min_row: (center_row - radius_cells).floor().clamp(0.0, max_index) as u32,
+        min_col: (center_col - radius_cells).floor().clamp(0.0, max_index) as u32,
+        max_row: (center_row + radius_cells).ceil().clamp(0.0, max_index) as u32,
+        max_col: (center_col + radius_cells).ceil().clamp(0.0, max_index) as u32,
+    };
+
+    let dt = time.delta_secs();
+    let mut updates: Vec<(usize, f32)> = Vec::new();
+
+    for row in rect.min_row..=rect.max_row {
+        for col in rect.min_col..=rect.max_col {
+            let dist = Vec2::new(col as f32 - center_col, row as f32 - center_row).length();
+            if dist >= radius_cells {
+                continue;
+            }
+            // Smooth falloff: 1 at the center, 0 at the rim
+            let t = dist / radius_cells;
+            let weight = (1.0 - t * t) * (1.0 - t * t);
+
+            let height = heightmap.get(row, col);
+            let blend = (brush.strength * BLEND_RATE * weight * dt).min(1.0);
+            let new_height = match brush.mode {
+                TerraformMode::Raise => height + brush.strength * weight * dt,
+                TerraformMode::Lower => height - brush.strength * weight * dt,
+                TerraformMode::Flatten => {
+                    let target = brush.flatten_target.unwrap_or(height);
+                    height + (target - height) * blend
+                }
+                TerraformMode::Smooth => {
+                    let average = (heightmap.get(row.saturating_sub(1), col)
+                        + heightmap.get(row + 1, col)
+                        + heightmap.get(row, col.saturating_sub(1))
+                        + heightmap.get(row, col + 1))
+                        / 4.0;
+                    height + (average - height) * blend
+                }
+            };
+
+            // Stay within the exportable range
+            updates.push(((row * res + col) as usize, new_height.clamp(0.0, config.height_scale)));
+        }
+    }
+
+    // Write after sampling so smoothing reads the heights from before this frame
+    for (index, height) in updates {
+        heightmap.heights[index] = height;
+    }
+
+    edits.dirty = Some(match edits.dirty {
+        Some(dirty) => dirty.union(rect),
+        None => rect,
+    });
+}
+
+/// Propagate pending terrain edits: reclassify biomes in the edited region,
+/// rebuild the terrain chunks it overlaps, and re-drape roads and
+/// buildings that sit on the edited ground.
+///
+/// Rivers, water bodies and resources are generation-time data and are not
+/// recomputed, so digging below sea level does not flood new ground.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_terrain_edits(
+    mut edits: ResMut<TerrainEdits>,
+    config: Res<TerrainConfig>,
+    heightmap: Res<Heightmap>,
+    mut biome_map: ResMut<BiomeMap>,
+    climate: Res<ClimateMap>,
+    resource_map: Res<ResourceMap>,
+    rivers: Res<RiverNetwork>,
+    water_bodies: Res<WaterBodies>,
+    mut road_network: ResMut<RoadNetwork>,
+    chunks: Query<(&TerrainChunk, &Mesh3d)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    lots: Query<&Lot>,
+    mut buildings: Query<&mut Transform, With<Building>>,
+) {
+    let Some(dirty) = edits.dirty.take() else {
+        return;
+    };
+
+    // Normals and slope-based biomes depend on neighboring heights
+    let res = heightmap.resolution;
+    let rect = dirty.expand(1, res);
+
+    for row in rect.min_row..=rect.max_row {
+        for col in rect.min_col..=rect.max_col {
+            biome_map.biomes[(row * res + col) as usize] =
+                classify_biome(&config, &heightmap, &climate, &rivers, &water_bodies, row, col);
+        }
+    }
+
+    for (chunk, mesh_handle) in &chunks {
+        if chunk.cells.intersects(&rect)
+            && let Some(mesh) = meshes.get_mut(&mesh_handle.0)
+        {
+            *mesh = build_chunk_mesh(&heightmap, &biome_map, &resource_map, &config, chunk.cells, chunk.lod);
+        }
+    }
+
+    // Only flag the network changed if a road actually crosses the edit,
+    // since that triggers a rebuild of the road meshes there.
+    let (min, max) = rect.world_bounds(res, config.map_size);
+    if road_network
+        .bypass_change_detection()
+        .drape_region(&heightmap, config.map_size, min, max)
+    {
+        road_network.set_changed();
+    }
+
+    for lot in &lots {
+        let reach = lot.half_extents.length();
+        let touched = lot.center.x + reach >= min.x
+            && lot.center.x - reach <= max.x
+            && lot.center.y + reach >= min.y
+            && lot.center.y - reach <= max.y;
+        if touched && let Ok(mut transform) = buildings.get_mut(lot.building) {
+            drape_building(&mut transform, lot, &heightmap, config.map_size);
+        }
+    }
+}
+
+/// Draw the brush outline draped on the terrain under the cursor.
+pub fn draw_terraform_brush(
+    active_tool: Res<ActiveTool>,
+    brush: Res<TerraformBrush>,
+    cursor_pos: Res<CursorWorldPosition>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
+    mut gizmos: Gizmos,
+) {
+    if *active_tool != ActiveTool::Terraform {
+        return;
+    }
+    let Some(center) = cursor_pos.position else {
+        return;
+    };
+
+    let color = Color::srgb(0.3, 0.8, 1.0);
+    let sides = 48;
+    let rim: Vec<Vec3> = (0..=sides)
+        .map(|i| {
+            let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+            let x = center.x + angle.cos() * brush.radius;
+            let z = center.z + angle.sin() * brush.radius;
+            Vec3::new(x, heightmap.sample_world(x, z, config.map_size) + 0.3, z)
+        })
+        .collect();
+    for pair in rim.windows(2) {
+        gizmos.line(pair[0], pair[1], color);
+    }
+    gizmos.sphere(Isometry3d::from_translation(center), 0.3, color);
+}
+
+/// Brush settings window, shown while the terraform tool is active.
+pub fn draw_terraform_panel(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mut brush: ResMut<TerraformBrush>,
+) -> Result {
+    if *active_tool != ActiveTool::Terraform {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Terraform")
+        .anchor(egui::Align2::LEFT_TOP, [88.0, 40.0])
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for mode in TerraformMode::ALL {
+                    if ui.selectable_label(brush.mode == mode, mode.label()).clicked() {
+                        brush.mode = mode;
+                    }
+                }
+            });
+            ui.add(egui::Slider::new(&mut brush.radius, MIN_BRUSH_RADIUS..=MAX_BRUSH_RADIUS).text("Radius"));
+            ui.add(
+                egui::Slider::new(&mut brush.strength, MIN_BRUSH_STRENGTH..=MAX_BRUSH_STRENGTH)
+                    .text("Strength"),
+            );
+        });
+
+    Ok(())
+}
@@ -1,12 +1,34 @@
 use bevy::prelude::*;
+use bevy::ecs::schedule::ScheduleLabel;
+#[cfg(feature = "gui")]
 use bevy::mesh::{Indices, PrimitiveTopology};
+#[cfg(feature = "gui")]
 use bevy::asset::RenderAssetUsages;
 use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
 
-/// Marker component for the terrain mesh entity, used for raycasting.
+#[cfg(feature = "gui")]
+use crate::camera::CityCamera;
+use crate::climate::ClimateMap;
+use crate::profile::GenerationProfile;
+#[cfg(feature = "gui")]
+use crate::resources::{resource_tint, ResourceMap};
+use crate::water::{RiverNetwork, WaterBodies};
+
+/// Marker component for terrain mesh entities (one per chunk).
+#[cfg(feature = "gui")]
 #[derive(Component)]
 pub struct TerrainMesh;
 
+/// Marker component for the water surface entity.
+#[cfg(feature = "gui")]
+#[derive(Component)]
+pub struct WaterSurface;
+
+/// Schedule holding the full world generation chain (heightmap → biomes →
+/// resources → meshes). Run once at startup and again for every new game.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GenerateWorld;
+
 /// Global configuration for terrain generation.
 ///
 /// - `map_size`: side length of the terrain in world units (square map).
@@ -14,13 +36,22 @@ pub struct TerrainMesh;
 ///   A 256x256 grid on a 500-unit map gives ~2 world units per cell.
 /// - `height_scale`: maximum terrain elevation. Raw noise values (0.0–1.0)
 ///   are multiplied by this to produce world-space heights.
+/// - `seed`: world seed. Every noise generator derives its own seed from this,
+///   so the same seed always reproduces the same map.
+/// - `erosion_droplets` / `thermal_iterations`: iteration counts for the
+///   erosion pass that runs after heightmap generation. Zero disables each.
+/// - `profile`: noise, shaping and biome parameters (see `GenerationProfile`).
 #[derive(Resource)]
 pub struct TerrainConfig {
     pub map_size: f32,
     pub resolution: u32,
     pub height_scale: f32,
-    /// Y elevation of the water surface. Terrain below this appears submerged.
+    /// Sea level. Terrain below this that connects to the map edge is ocean.
     pub water_level: f32,
+    pub seed: u32,
+    pub erosion_droplets: u32,
+    pub thermal_iterations: u32,
+    pub profile: GenerationProfile,
 }
 
 impl Default for TerrainConfig {
@@ -30,10 +61,28 @@ impl Default for TerrainConfig {
             resolution: 256,
             height_scale: 30.0,
             water_level: 10.0,
+            seed: 42,
+            erosion_droplets: 40_000,
+            thermal_iterations: 20,
+            profile: GenerationProfile::default(),
         }
     }
 }
 
+impl TerrainConfig {
+    /// Derive an independent noise seed from the world seed.
+    ///
+    /// Each generator passes its own `stream` number so generators stay
+    /// uncorrelated with each other. Uses a SplitMix64 finalizer, so adjacent
+    /// world seeds still produce unrelated maps.
+    pub fn derive_seed(&self, stream: u32) -> u32 {
+        let mut z = (((self.seed as u64) << 32) | stream as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as u32
+    }
+}
+
 /// Terrain biome classification for each grid cell.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Biome {
@@ -45,6 +94,14 @@ pub enum Biome {
     Dirt,
     /// Steep slopes or high elevation
     Rock,
+    /// Moist ground away from the shore, flat or sloped
+    Forest,
+    /// Waterlogged flats just above the water line
+    Marsh,
+    /// Moist, warm flats near water
+    Meadow,
+    /// Dry or hot ground with sparse vegetation
+    Scrub,
 }
 
 impl Biome {
@@ -55,6 +112,10 @@ impl Biome {
             Biome::Grass => [0.30, 0.50, 0.20, 1.0], // green
             Biome::Dirt  => [0.55, 0.40, 0.25, 1.0], // earthy brown
             Biome::Rock  => [0.50, 0.48, 0.45, 1.0], // gray stone
+            Biome::Forest => [0.16, 0.34, 0.14, 1.0], // dark green
+            Biome::Marsh  => [0.32, 0.40, 0.28, 1.0], // muddy olive
+            Biome::Meadow => [0.45, 0.60, 0.28, 1.0], // bright green
+            Biome::Scrub  => [0.58, 0.54, 0.36, 1.0], // dry khaki
         }
     }
 }
@@ -94,6 +155,7 @@ impl Heightmap {
 
     /// Sample terrain flatness at a world-space (x, z) position.
     /// Returns the Y component of the surface normal (1.0 = flat, 0.0 = cliff).
+    #[cfg(feature = "gui")]
     pub fn sample_flatness(&self, x: f32, z: f32, map_size: f32) -> f32 {
         let half = map_size / 2.0;
         let cell_size = map_size / self.resolution as f32;
@@ -140,18 +202,202 @@ impl Heightmap {
 
         h0 * (1.0 - fz) + h1 * fz
     }
+
+    /// Intersect a world-space ray with the terrain surface.
+    ///
+    /// Marches the ray across the grid one cell at a time (2D DDA on the XZ
+    /// plane) until it passes below the surface, then bisects inside that
+    /// cell on the bilinear height to refine the hit. A ray that enters the
+    /// map already below the surface hits at its entry point. Returns the
+    /// first hit point, or `None` if the ray misses the map. Needs only the
+    /// heights, so it works without a render mesh and at any terrain LOD.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn raycast(&self, ray: Ray3d, map_size: f32) -> Option<Vec3> {
+        let half = map_size / 2.0;
+        let cell_size = map_size / self.resolution as f32;
+        let cells = self.resolution as i32 - 1;
+        let origin = ray.origin;
+        let dir = *ray.direction;
+
+        // Height of the ray above the terrain at parameter t
+        let clearance = |t: f32| {
+            let p = origin + dir * t;
+            p.y - self.sample_world(p.x, p.z, map_size)
+        };
+
+        // Map extent covered by grid vertices
+        let min = -half;
+        let max = -half + cells as f32 * cell_size;
+
+        // Straight up or down: no cells to march, just test the one below
+        if dir.x.abs() < f32::EPSILON && dir.z.abs() < f32::EPSILON {
+            let inside = (min..=max).contains(&origin.x) && (min..=max).contains(&origin.z);
+            let ground = self.sample_world(origin.x, origin.z, map_size);
+            return (inside && (dir.y < 0.0 || origin.y < ground))
+                .then(|| Vec3::new(origin.x, ground, origin.z));
+        }
+
+        // Clip the ray to the map square (slab test on X and Z)
+        let mut t_enter = 0.0f32;
+        let mut t_exit = f32::INFINITY;
+        for (o, d) in [(origin.x, dir.x), (origin.z, dir.z)] {
+            if d.abs() < f32::EPSILON {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min - o) / d;
+            let t1 = (max - o) / d;
+            t_enter = t_enter.max(t0.min(t1));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        if t_enter > t_exit {
+            return None;
+        }
+
+        // Starting cell and the ray parameter at its next X and Z boundaries
+        let entry = origin + dir * t_enter;
+        let mut col = (((entry.x - min) / cell_size) as i32).clamp(0, cells - 1);
+        let mut row = (((entry.z - min) / cell_size) as i32).clamp(0, cells - 1);
+
+        let axis_setup = |index: i32, o: f32, d: f32| -> (i32, f32, f32) {
+            if d > 0.0 {
+                let boundary = min + (index + 1) as f32 * cell_size;
+                (1, (boundary - o) / d, cell_size / d)
+            } else if d < 0.0 {
+                let boundary = min + index as f32 * cell_size;
+                (-1, (boundary - o) / d, -cell_size / d)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (step_col, mut t_next_col, t_delta_col) = axis_setup(col, origin.x, dir.x);
+        let (step_row, mut t_next_row, t_delta_row) = axis_setup(row, origin.z, dir.z);
+
+        let mut t0 = t_enter;
+        let mut above0 = clearance(t0);
+        if above0 < 0.0 {
+            return Some(Vec3::new(entry.x, self.sample_world(entry.x, entry.z, map_size), entry.z));
+        }
+        loop {
+            let t1 = t_next_col.min(t_next_row).min(t_exit);
+            let above1 = clearance(t1);
+
+            if above0 >= 0.0 && above1 < 0.0 {
+                // Crossed the surface inside this cell; bisect to refine
+                let (mut lo, mut hi) = (t0, t1);
+                for _ in 0..20 {
+                    let mid = (lo + hi) / 2.0;
+                    if clearance(mid) >= 0.0 {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                let hit = origin + dir * hi;
+                return Some(Vec3::new(hit.x, self.sample_world(hit.x, hit.z, map_size), hit.z));
+            }
+
+            if t1 >= t_exit {
+                return None;
+            }
+            if t_next_col < t_next_row {
+                col += step_col;
+                t_next_col += t_delta_col;
+            } else {
+                row += step_row;
+                t_next_row += t_delta_row;
+            }
+            if col < 0 || row < 0 || col >= cells || row >= cells {
+                return None;
+            }
+
+            t0 = t1;
+            above0 = above1;
+        }
+    }
+}
+
+/// An inclusive rectangle of heightmap cells: the part of the terrain an edit
+/// touched, a chunk's extent, or a deposit's bounding box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellRect {
+    pub min_row: u32,
+    pub min_col: u32,
+    pub max_row: u32,
+    pub max_col: u32,
+}
+
+impl CellRect {
+    /// The rectangle covering a single cell.
+    pub fn cell(row: u32, col: u32) -> CellRect {
+        CellRect {
+            min_row: row,
+            min_col: col,
+            max_row: row,
+            max_col: col,
+        }
+    }
+
+    /// Smallest rectangle covering both.
+    pub fn union(self, other: CellRect) -> CellRect {
+        CellRect {
+            min_row: self.min_row.min(other.min_row),
+            min_col: self.min_col.min(other.min_col),
+            max_row: self.max_row.max(other.max_row),
+            max_col: self.max_col.max(other.max_col),
+        }
+    }
+
+    /// Whether the two rectangles share any cell.
+    #[cfg(feature = "gui")]
+    pub fn intersects(&self, other: &CellRect) -> bool {
+        self.min_row <= other.max_row
+            && other.min_row <= self.max_row
+            && self.min_col <= other.max_col
+            && other.min_col <= self.max_col
+    }
+
+    /// Grow by `cells` on every side, clamped to a `resolution`-sized grid.
+    #[cfg(feature = "gui")]
+    pub fn expand(self, cells: u32, resolution: u32) -> CellRect {
+        CellRect {
+            min_row: self.min_row.saturating_sub(cells),
+            min_col: self.min_col.saturating_sub(cells),
+            max_row: (self.max_row + cells).min(resolution - 1),
+            max_col: (self.max_col + cells).min(resolution - 1),
+        }
+    }
+
+    /// World-space (x, z) corners of the rectangle as (min, max).
+    pub fn world_bounds(&self, resolution: u32, map_size: f32) -> (Vec2, Vec2) {
+        let half = map_size / 2.0;
+        let cell_size = map_size / resolution as f32;
+        (
+            Vec2::new(self.min_col as f32 * cell_size - half, self.min_row as f32 * cell_size - half),
+            Vec2::new(self.max_col as f32 * cell_size - half, self.max_row as f32 * cell_size - half),
+        )
+    }
 }
 
 /// Generate a heightmap from the terrain config using fBm Perlin noise.
 ///
 /// This runs once at startup. It creates an `Fbm<Perlin>` noise generator,
 /// samples it at each grid point, remaps the [-1,1] noise output to [0,1],
-/// and scales by `height_scale`.
+/// shapes it by the generation profile, and scales by `height_scale`.
 pub fn generate_heightmap(mut commands: Commands, config: Res<TerrainConfig>) {
-    let noise = Fbm::<Perlin>::new(42)
-        .set_octaves(6)
-        .set_frequency(2.0 / config.map_size as f64) // ~2 hills across the map
-        .set_persistence(0.5);
+    commands.insert_resource(noise_heightmap(&config));
+}
+
+/// Build a noise heightmap for the given config. Shared by `generate_heightmap`
+/// and the heightmap import fallback.
+pub fn noise_heightmap(config: &TerrainConfig) -> Heightmap {
+    let profile = &config.profile;
+    let noise = Fbm::<Perlin>::new(config.derive_seed(0))
+        .set_octaves(profile.octaves)
+        .set_frequency(profile.frequency / config.map_size as f64)
+        .set_persistence(profile.persistence);
 
     let res = config.resolution;
     let cell_size = config.map_size / res as f32;
@@ -167,68 +413,62 @@ pub fn generate_heightmap(mut commands: Commands, config: Res<TerrainConfig>) {
             let sample = noise.get([x, z]);
             let normalized = ((sample + 1.0) / 2.0).clamp(0.0, 1.0) as f32;
 
-            heights.push(normalized * config.height_scale);
+            let u = col as f32 / (res - 1) as f32;
+            let v = row as f32 / (res - 1) as f32;
+            heights.push(shape_height(profile, normalized, u, v) * config.height_scale);
         }
     }
 
-    commands.insert_resource(Heightmap {
+    Heightmap {
         heights,
         resolution: res,
-    });
+    }
+}
+
+/// Apply the profile's bias, exponent, edge lift and tilt to a normalized
+/// height at map position (`u`, `v`) in 0.0–1.0. The default profile leaves
+/// heights unchanged.
+fn shape_height(profile: &GenerationProfile, height: f32, u: f32, v: f32) -> f32 {
+    let mut t = (height + profile.elevation_bias).clamp(0.0, 1.0);
+    t = t.powf(profile.elevation_exponent);
+
+    // 0 at the center, 1 at the middle of each edge
+    let edge = ((Vec2::new(u, v) - 0.5).length() * 2.0).min(1.0);
+    t += profile.edge_lift * edge * edge;
+    t -= profile.tilt * v;
+
+    t.clamp(0.0, 1.0)
 }
 
-/// Classify each grid cell into a biome based on elevation, slope, and water level.
+/// Classify each grid cell into a biome based on elevation, slope, water,
+/// moisture and temperature.
 ///
 /// Rules:
-/// - Below or just above water level → Sand (shoreline)
-/// - Steep slope (normal Y < 0.85) → Rock (cliffs)
-/// - High elevation (top 30%) → Rock
-/// - Mid elevation or moderate slope → Dirt
-/// - Everything else → Grass
+/// - Under water, on a shoreline, or in a river channel → Sand
+/// - Very flat, very moist (> 0.75) and within 3 units of sea level → Marsh
+/// - Just above sea level (within the profile's `shore_margin`) → Sand
+/// - Steep slope (normal Y < 0.85) or high elevation (top 30%) → Rock
+/// - Cold (temperature < 0.25) → Dirt on slopes / mid elevation, Grass on flats
+/// - Hot and dry (temperature > 0.75, moisture < 0.35) or very dry (< 0.2) → Scrub
+/// - Mid elevation or moderate slope (normal Y < 0.93 or top 50%) → Forest if moisture > 0.35, else Dirt
+/// - Flats → Meadow if moisture > 0.6, Forest if > 0.4, else Grass
+///
+/// Slope and elevation thresholds shown are the defaults; each comes from
+/// `TerrainConfig::profile`.
 pub fn generate_biome_map(
     mut commands: Commands,
     config: Res<TerrainConfig>,
     heightmap: Res<Heightmap>,
+    climate: Res<ClimateMap>,
+    rivers: Res<RiverNetwork>,
+    water_bodies: Res<WaterBodies>,
 ) {
     let res = heightmap.resolution;
-    let cell_size = config.map_size / res as f32;
     let mut biomes = Vec::with_capacity((res * res) as usize);
 
     for row in 0..res {
         for col in 0..res {
-            let height = heightmap.get(row, col);
-
-            // Compute slope from neighbor heights (same as normal calculation)
-            let h_left = if col > 0 { heightmap.get(row, col - 1) } else { height };
-            let h_right = if col < res - 1 { heightmap.get(row, col + 1) } else { height };
-            let h_down = if row > 0 { heightmap.get(row - 1, col) } else { height };
-            let h_up = if row < res - 1 { heightmap.get(row + 1, col) } else { height };
-
-            let normal = Vec3::new(
-                h_left - h_right,
-                2.0 * cell_size,
-                h_down - h_up,
-            )
-            .normalize();
-
-            // normal.y: 1.0 = flat, 0.0 = vertical cliff
-            let flatness = normal.y;
-            let elevation_t = height / config.height_scale;
-            let shore_margin = 2.0; // world units above water = sand
-
-            let biome = if height < config.water_level + shore_margin {
-                Biome::Sand
-            } else if flatness < 0.85 {
-                Biome::Rock
-            } else if elevation_t > 0.7 {
-                Biome::Rock
-            } else if flatness < 0.93 || elevation_t > 0.5 {
-                Biome::Dirt
-            } else {
-                Biome::Grass
-            };
-
-            biomes.push(biome);
+            biomes.push(classify_biome(&config, &heightmap, &climate, &rivers, &water_bodies, row, col));
         }
     }
 
@@ -238,94 +478,204 @@ pub fn generate_biome_map(
     });
 }
 
-/// Build a terrain mesh from the heightmap and spawn it into the world.
+/// Classify a single grid cell. Shared by `generate_biome_map` and the
+/// terraform tool, which reclassifies only the cells it edited.
+pub fn classify_biome(
+    config: &TerrainConfig,
+    heightmap: &Heightmap,
+    climate: &ClimateMap,
+    rivers: &RiverNetwork,
+    water_bodies: &WaterBodies,
+    row: u32,
+    col: u32,
+) -> Biome {
+    let res = heightmap.resolution;
+    let cell_size = config.map_size / res as f32;
+    let height = heightmap.get(row, col);
+
+    // normal.y: 1.0 = flat, 0.0 = vertical cliff
+    let flatness = vertex_normal(heightmap, row, col, cell_size).y;
+    let elevation_t = height / config.height_scale;
+    let profile = &config.profile;
+
+    let moisture = climate.moisture(row, col);
+    let temperature = climate.temperature_at(row, col, elevation_t);
+
+    let is_wet = water_bodies.is_water(row, col)
+        || water_bodies.is_shoreline(row, col)
+        || rivers.is_river(row, col);
+    let is_sloped = flatness < profile.slope_flatness || elevation_t > profile.upland_elevation;
+
+    if is_wet {
+        Biome::Sand
+    } else if flatness > profile.marsh_flatness
+        && moisture > profile.marsh_moisture
+        && height < config.water_level + profile.marsh_margin
+    {
+        Biome::Marsh
+    } else if height < config.water_level + profile.shore_margin {
+        Biome::Sand
+    } else if flatness < profile.rock_flatness || elevation_t > profile.rock_elevation {
+        Biome::Rock
+    } else if temperature < profile.cold_temperature {
+        if is_sloped { Biome::Dirt } else { Biome::Grass }
+    } else if moisture < profile.scrub_moisture
+        || (temperature > profile.hot_temperature && moisture < profile.hot_scrub_moisture)
+    {
+        Biome::Scrub
+    } else if is_sloped {
+        if moisture > profile.slope_forest_moisture { Biome::Forest } else { Biome::Dirt }
+    } else if moisture > profile.meadow_moisture {
+        Biome::Meadow
+    } else if moisture > profile.forest_moisture {
+        Biome::Forest
+    } else {
+        Biome::Grass
+    }
+}
+
+/// Approximate the surface normal at a grid vertex from its neighbors' heights.
 ///
-/// For each grid point, creates a vertex at (x, height, z). Connects
-/// adjacent vertices into triangles (2 per grid cell). Computes per-vertex
-/// normals from neighboring heights so lighting follows the terrain slope.
-pub fn spawn_terrain_mesh(
-    mut commands: Commands,
-    config: Res<TerrainConfig>,
-    heightmap: Res<Heightmap>,
-    biome_map: Res<BiomeMap>,
-    resource_map: Res<crate::resources::ResourceMap>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
+/// The differences in height along X and Z give two tangent vectors; their
+/// cross product is the normal:
+/// - Tangent along X: (2*cell_size, h_right - h_left, 0)
+/// - Tangent along Z: (0, h_up - h_down, 2*cell_size)
+fn vertex_normal(heightmap: &Heightmap, row: u32, col: u32, cell_size: f32) -> Vec3 {
+    let res = heightmap.resolution;
+    let height = heightmap.get(row, col);
+    let h_left = if col > 0 { heightmap.get(row, col - 1) } else { height };
+    let h_right = if col < res - 1 { heightmap.get(row, col + 1) } else { height };
+    let h_down = if row > 0 { heightmap.get(row - 1, col) } else { height };
+    let h_up = if row < res - 1 { heightmap.get(row + 1, col) } else { height };
+
+    Vec3::new(
+        h_left - h_right,      // dx component
+        2.0 * cell_size,       // y component (always positive = up)
+        h_down - h_up,         // dz component
+    )
+    .normalize()
+}
+
+/// Vertex color for a grid cell: the biome color with any resource color
+/// blended in by richness.
+#[cfg(feature = "gui")]
+fn vertex_color(biome_map: &BiomeMap, resource_map: &ResourceMap, row: u32, col: u32) -> [f32; 4] {
+    let biome_color = biome_map.get(row, col).color();
+    if let Some(rc) = resource_tint(resource_map.known(row, col)) {
+        let t = rc[3] * 0.7; // partial blend to keep biome visible
+        [
+            biome_color[0] * (1.0 - t) + rc[0] * t,
+            biome_color[1] * (1.0 - t) + rc[1] * t,
+            biome_color[2] * (1.0 - t) + rc[2] * t,
+            1.0,
+        ]
+    } else {
+        biome_color
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Chunked terrain mesh
+// ---------------------------------------------------------------------------
+
+/// Grid cells along each side of a terrain chunk. A 256 heightmap splits
+/// into 8x8 chunks.
+#[cfg(feature = "gui")]
+const CHUNK_CELLS: u32 = 32;
+
+/// Camera distances (world units) at which chunks drop to the next LOD.
+/// LOD n samples every 2^n-th heightmap vertex.
+#[cfg(feature = "gui")]
+const LOD_DISTANCES: [f32; 3] = [150.0, 300.0, 500.0];
+
+/// How far chunk skirts hang below the surface. Must exceed the largest
+/// height error between neighboring LODs so cracks are never visible.
+#[cfg(feature = "gui")]
+const SKIRT_DEPTH: f32 = 8.0;
+
+/// A square block of the terrain mesh, covering an inclusive range of
+/// heightmap vertices. Neighboring chunks share their border vertices.
+#[cfg(feature = "gui")]
+#[derive(Component)]
+pub struct TerrainChunk {
+    pub cells: CellRect,
+    /// Current level of detail (0 = full resolution).
+    pub lod: u32,
+}
+
+/// LOD for a chunk at the given distance from the camera.
+#[cfg(feature = "gui")]
+fn lod_for_distance(distance: f32) -> u32 {
+    LOD_DISTANCES.iter().take_while(|&&d| distance > d).count() as u32
+}
+
+/// Heightmap indices sampled along one chunk axis at a given step, always
+/// ending on `max` so neighboring chunks meet at the same vertices.
+#[cfg(feature = "gui")]
+pub fn lod_axis(min: u32, max: u32, step: u32) -> Vec<u32> {
+    let mut axis: Vec<u32> = (min..max).step_by(step as usize).collect();
+    axis.push(max);
+    axis
+}
+
+/// Build the mesh for one terrain chunk at the given LOD.
+///
+/// For each sampled grid point, creates a vertex at (x, height, z) and
+/// connects neighbors into triangles (2 per quad). Normals come from the
+/// full-resolution heightmap so lighting stays the same at every LOD. A skirt
+/// of downward-facing quads around the border hides cracks where a chunk
+/// meets a coarser neighbor.
+#[cfg(feature = "gui")]
+pub fn build_chunk_mesh(
+    heightmap: &Heightmap,
+    biome_map: &BiomeMap,
+    resource_map: &ResourceMap,
+    config: &TerrainConfig,
+    cells: CellRect,
+    lod: u32,
+) -> Mesh {
     let res = heightmap.resolution;
     let cell_size = config.map_size / res as f32;
     // Center the terrain on the origin
     let half = config.map_size / 2.0;
 
-    let vertex_count = (res * res) as usize;
+    let step = 1 << lod;
+    let rows = lod_axis(cells.min_row, cells.max_row, step);
+    let cols = lod_axis(cells.min_col, cells.max_col, step);
+    let width = cols.len() as u32;
+    let depth = rows.len() as u32;
+
+    let vertex_count = (width * depth + 2 * (width + depth)) as usize;
     let mut positions: Vec<[f32; 3]> = Vec::with_capacity(vertex_count);
     let mut normals: Vec<[f32; 3]> = Vec::with_capacity(vertex_count);
     let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(vertex_count);
     let mut colors: Vec<[f32; 4]> = Vec::with_capacity(vertex_count);
 
-    // --- Vertices & colors ---
-    for row in 0..res {
-        for col in 0..res {
+    // --- Vertices, normals & colors ---
+    for &row in &rows {
+        for &col in &cols {
             let x = col as f32 * cell_size - half;
             let z = row as f32 * cell_size - half;
             let y = heightmap.get(row, col);
 
             positions.push([x, y, z]);
+            normals.push(vertex_normal(heightmap, row, col, cell_size).into());
             uvs.push([col as f32 / (res - 1) as f32, row as f32 / (res - 1) as f32]);
 
             // Blend resource color into biome color based on richness
-            let biome_color = biome_map.get(row, col).color();
-            let color = if let Some(cell) = resource_map.get(row, col) {
-                let rc = cell.resource.color();
-                let t = cell.richness * 0.7; // partial blend to keep biome visible
-                [
-                    biome_color[0] * (1.0 - t) + rc[0] * t,
-                    biome_color[1] * (1.0 - t) + rc[1] * t,
-                    biome_color[2] * (1.0 - t) + rc[2] * t,
-                    1.0,
-                ]
-            } else {
-                biome_color
-            };
-            colors.push(color);
-        }
-    }
-
-    // --- Normals ---
-    // For each vertex, approximate the surface normal by sampling the height
-    // of its neighbors. The differences in height along X and Z give us two
-    // tangent vectors; their cross product is the normal.
-    for row in 0..res {
-        for col in 0..res {
-            let h_left = if col > 0 { heightmap.get(row, col - 1) } else { heightmap.get(row, col) };
-            let h_right = if col < res - 1 { heightmap.get(row, col + 1) } else { heightmap.get(row, col) };
-            let h_down = if row > 0 { heightmap.get(row - 1, col) } else { heightmap.get(row, col) };
-            let h_up = if row < res - 1 { heightmap.get(row + 1, col) } else { heightmap.get(row, col) };
-
-            // Tangent along X: (2*cell_size, h_right - h_left, 0)
-            // Tangent along Z: (0, h_up - h_down, 2*cell_size)
-            // Normal = cross(tangent_z, tangent_x) to get outward-facing Y-up
-            let normal = Vec3::new(
-                h_left - h_right,      // dx component
-                2.0 * cell_size,       // y component (always positive = up)
-                h_down - h_up,         // dz component
-            )
-            .normalize();
-
-            normals.push(normal.into());
+            colors.push(vertex_color(biome_map, resource_map, row, col));
         }
     }
 
     // --- Indices ---
-    // Two triangles per grid cell, winding counter-clockwise
-    let cell_count = ((res - 1) * (res - 1)) as usize;
-    let mut indices: Vec<u32> = Vec::with_capacity(cell_count * 6);
+    // Two triangles per quad, winding counter-clockwise
+    let mut indices: Vec<u32> = Vec::with_capacity(((width - 1) * (depth - 1) * 6) as usize);
 
-    for row in 0..(res - 1) {
-        for col in 0..(res - 1) {
-            let top_left = row * res + col;
+    for r in 0..(depth - 1) {
+        for c in 0..(width - 1) {
+            let top_left = r * width + c;
             let top_right = top_left + 1;
-            let bottom_left = (row + 1) * res + col;
+            let bottom_left = (r + 1) * width + c;
             let bottom_right = bottom_left + 1;
 
             // Triangle 1: top-left, bottom-left, top-right
@@ -340,44 +690,205 @@ pub fn spawn_terrain_mesh(
         }
     }
 
-    // --- Build mesh ---
-    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+    // --- Skirt ---
+    // Walk the border clockwise seen from above (north edge west→east, east
+    // edge north→south, ...) so each quad faces outward.
+    let mut border: Vec<u32> = Vec::with_capacity(2 * (width + depth) as usize);
+    border.extend(0..width);
+    border.extend((1..depth).map(|r| r * width + width - 1));
+    border.extend((0..width - 1).rev().map(|c| (depth - 1) * width + c));
+    border.extend((1..depth - 1).rev().map(|r| r * width));
+
+    let skirt_base = positions.len() as u32;
+    for &i in &border {
+        let [x, y, z] = positions[i as usize];
+        positions.push([x, y - SKIRT_DEPTH, z]);
+        normals.push(normals[i as usize]);
+        uvs.push(uvs[i as usize]);
+        colors.push(colors[i as usize]);
+    }
+
+    let border_len = border.len() as u32;
+    for k in 0..border_len {
+        let next = (k + 1) % border_len;
+        let (top_a, top_b) = (border[k as usize], border[next as usize]);
+        let (low_a, low_b) = (skirt_base + k, skirt_base + next);
+
+        indices.extend_from_slice(&[top_a, top_b, low_a]);
+        indices.extend_from_slice(&[top_b, low_b, low_a]);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
         .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
         .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
         .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
         .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
-        .with_inserted_indices(Indices::U32(indices));
-
-    // White base color so vertex colors drive the appearance
-    commands.spawn((
-        Mesh3d(meshes.add(mesh)),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: Color::WHITE,
-            perceptual_roughness: 0.9,
-            ..default()
-        })),
-        TerrainMesh,
-    ));
+        .with_inserted_indices(Indices::U32(indices))
 }
 
-/// Spawn a flat blue plane at `water_level` to represent bodies of water.
-///
-/// Sized to cover the full terrain. Semi-transparent so you can see the
-/// terrain underneath in shallow areas.
-pub fn spawn_water_plane(
+/// Split the terrain into `CHUNK_CELLS`-sized chunks and spawn each as its
+/// own mesh entity at full detail. `update_terrain_lod` lowers the detail of
+/// distant chunks from the next frame on.
+#[cfg(feature = "gui")]
+pub fn spawn_terrain_mesh(
     mut commands: Commands,
     config: Res<TerrainConfig>,
+    heightmap: Res<Heightmap>,
+    biome_map: Res<BiomeMap>,
+    resource_map: Res<ResourceMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    commands.spawn((
-        Mesh3d(meshes.add(Plane3d::default().mesh().size(config.map_size, config.map_size))),
-        MeshMaterial3d(materials.add(StandardMaterial {
-            base_color: Color::srgba(0.1, 0.3, 0.5, 0.7),
-            alpha_mode: AlphaMode::Blend,
-            perceptual_roughness: 0.3,
-            ..default()
-        })),
-        Transform::from_xyz(0.0, config.water_level, 0.0),
-    ));
+    let last = heightmap.resolution - 1;
+
+    // White base color so vertex colors drive the appearance
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+
+    for min_row in (0..last).step_by(CHUNK_CELLS as usize) {
+        for min_col in (0..last).step_by(CHUNK_CELLS as usize) {
+            let cells = CellRect {
+                min_row,
+                min_col,
+                max_row: (min_row + CHUNK_CELLS).min(last),
+                max_col: (min_col + CHUNK_CELLS).min(last),
+            };
+            let mesh = build_chunk_mesh(&heightmap, &biome_map, &resource_map, &config, cells, 0);
+
+            commands.spawn((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(material.clone()),
+                TerrainMesh,
+                TerrainChunk { cells, lod: 0 },
+            ));
+        }
+    }
+}
+
+/// Pick each chunk's LOD from its distance to the camera and rebuild the
+/// chunks whose LOD changed.
+#[cfg(feature = "gui")]
+pub fn update_terrain_lod(
+    camera_query: Query<&GlobalTransform, With<CityCamera>>,
+    config: Res<TerrainConfig>,
+    heightmap: Res<Heightmap>,
+    biome_map: Res<BiomeMap>,
+    resource_map: Res<ResourceMap>,
+    mut chunks: Query<(&mut TerrainChunk, &Mesh3d)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(camera) = camera_query.single() else {
+        return;
+    };
+    let camera_pos = camera.translation();
+
+    for (mut chunk, mesh_handle) in &mut chunks {
+        let (min, max) = chunk.cells.world_bounds(heightmap.resolution, config.map_size);
+        let center = (min + max) / 2.0;
+        let center_y = heightmap.sample_world(center.x, center.y, config.map_size);
+        let distance = camera_pos.distance(Vec3::new(center.x, center_y, center.y));
+
+        let lod = lod_for_distance(distance);
+        if lod == chunk.lod {
+            continue;
+        }
+        chunk.lod = lod;
+
+        if let Some(mesh) = meshes.get_mut(&mesh_handle.0) {
+            *mesh = build_chunk_mesh(&heightmap, &biome_map, &resource_map, &config, chunk.cells, lod);
+        }
+    }
+}
+
+/// Run the `GenerateWorld` schedule. Added to `Startup` to build the first map.
+#[cfg(feature = "gui")]
+pub fn generate_world(world: &mut World) {
+    world.run_schedule(GenerateWorld);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 65x65 heightmap over a 130-unit map (2 units per cell) from a height function.
+    fn heightmap_from(height: impl Fn(f32, f32) -> f32) -> Heightmap {
+        let res = 65;
+        let mut heights = Vec::new();
+        for row in 0..res {
+            for col in 0..res {
+                heights.push(height(col as f32 * 2.0 - 65.0, row as f32 * 2.0 - 65.0));
+            }
+        }
+        Heightmap { heights, resolution: res }
+    }
+
+    fn ray(origin: Vec3, toward: Vec3) -> Ray3d {
+        Ray3d::new(origin, Dir3::new(toward - origin).unwrap())
+    }
+
+    #[test]
+    fn raycast_hits_flat_ground() {
+        let heightmap = heightmap_from(|_, _| 5.0);
+        let hit = heightmap
+            .raycast(ray(Vec3::new(-40.0, 50.0, -30.0), Vec3::new(10.0, 5.0, 20.0)), 130.0)
+            .unwrap();
+        assert!(hit.distance(Vec3::new(10.0, 5.0, 20.0)) < 0.01);
+    }
+
+    #[test]
+    fn raycast_hits_straight_down() {
+        let heightmap = heightmap_from(|x, _| x * 0.1 + 10.0);
+        let hit = heightmap
+            .raycast(Ray3d::new(Vec3::new(12.0, 100.0, -7.0), Dir3::NEG_Y), 130.0)
+            .unwrap();
+        assert!(hit.distance(Vec3::new(12.0, 11.2, -7.0)) < 0.01);
+    }
+
+    #[test]
+    fn raycast_matches_sampled_height_on_slope() {
+        let heightmap = heightmap_from(|x, z| 10.0 + x * 0.2 - z * 0.1);
+        let hit = heightmap
+            .raycast(ray(Vec3::new(-60.0, 60.0, 50.0), Vec3::new(20.0, 0.0, -25.0)), 130.0)
+            .unwrap();
+        let ground = heightmap.sample_world(hit.x, hit.z, 130.0);
+        assert!((hit.y - ground).abs() < 1e-4);
+    }
+
+    #[test]
+    fn raycast_stops_at_first_hill() {
+        // Ridge along x = 0 blocks the view of the ground behind it
+        let heightmap = heightmap_from(|x, _| if x.abs() < 4.0 { 20.0 } else { 0.0 });
+        let hit = heightmap
+            .raycast(ray(Vec3::new(-50.0, 15.0, 0.0), Vec3::new(50.0, 0.0, 0.0)), 130.0)
+            .unwrap();
+        assert!(hit.x < 0.0 && hit.x > -6.0, "hit {hit:?}");
+    }
+
+    #[test]
+    fn raycast_misses_outside_map_and_upward() {
+        let heightmap = heightmap_from(|_, _| 5.0);
+        // Heads away from the map, which it would otherwise enter underground
+        let outside = ray(Vec3::new(-200.0, 50.0, 0.0), Vec3::new(-250.0, 0.0, 0.0));
+        assert_eq!(heightmap.raycast(outside, 130.0), None);
+
+        let upward = ray(Vec3::new(0.0, 10.0, 0.0), Vec3::new(20.0, 40.0, 0.0));
+        assert_eq!(heightmap.raycast(upward, 130.0), None);
+    }
+
+    #[test]
+    fn raycast_hits_at_entry_when_starting_underground() {
+        let heightmap = heightmap_from(|_, _| 5.0);
+        // Origin inside the map but below the surface, even looking up
+        let buried = ray(Vec3::new(10.0, 2.0, -4.0), Vec3::new(30.0, 40.0, -4.0));
+        let hit = heightmap.raycast(buried, 130.0).unwrap();
+        assert!(hit.distance(Vec3::new(10.0, 5.0, -4.0)) < 0.01);
+
+        // Entering through the map edge below the surface hits at the edge
+        let low = ray(Vec3::new(-100.0, 1.0, 0.0), Vec3::new(0.0, 3.0, 0.0));
+        let hit = heightmap.raycast(low, 130.0).unwrap();
+        assert!((hit.x + 65.0).abs() < 0.01 && (hit.y - 5.0).abs() < 0.01, "hit {hit:?}");
+    }
 }
@@ -1,14 +1,19 @@
-use bevy::picking::mesh_picking::ray_cast::{MeshRayCast, MeshRayCastSettings};
 use bevy::prelude::*;
 use bevy_egui::input::EguiWantsInput;
 use bevy_egui::{egui, EguiContexts};
 
-use crate::building::Building;
+use crate::building::{Building, Disconnected, Exhausted};
 use crate::camera::CityCamera;
 use crate::economy::{BuildingCategory, EconomyDebug, BUILDING_DEFS};
-use crate::resources::ResourceMap;
-use crate::road::{ActiveTool, RoadNetwork, RoadPlacementState};
-use crate::terrain::{Heightmap, TerrainConfig, TerrainMesh};
+use crate::heightmap_io::export_heightmap;
+use crate::notifications::Notifications;
+use crate::profile::GenerationProfile;
+use crate::resources::{ResourceDeposits, ResourceMap, ResourceOverlay, ResourceType};
+use crate::road::{ActiveTool, RoadNetwork, RoadPlacementState, SEGMENT_PICK_RADIUS};
+use crate::road_upgrade::RoadUpgrades;
+use crate::terrain::{Heightmap, TerrainConfig};
+use crate::water::WaterBodies;
+use crate::{NewGame, NewGameRequest};
 
 /// Game simulation speed levels.
 #[derive(Clone, Copy, Default, PartialEq, Eq)]
@@ -45,7 +50,28 @@ pub struct GameTime {
     pub speed: GameSpeed,
 }
 
-/// Master debug toggle (F3): economy panel, road/lot/resource gizmos.
+/// City treasury, spent on player-built infrastructure such as road upgrades.
+#[derive(Resource)]
+pub struct CityBudget {
+    pub funds: f32,
+}
+
+impl Default for CityBudget {
+    fn default() -> Self {
+        Self { funds: 5000.0 }
+    }
+}
+
+/// State of the new-game dialog: whether it is open, and the seed and
+/// generation profile being edited.
+#[derive(Resource, Default)]
+pub struct NewGameDialog {
+    pub open: bool,
+    pub seed_input: String,
+    pub profile: GenerationProfile,
+}
+
+/// Master debug toggle (F3): economy panel, resource overlay, road/lot gizmos.
 #[derive(Resource, Default)]
 pub struct DebugVisible(pub bool);
 
@@ -87,13 +113,13 @@ pub struct CursorWorldPosition {
     pub position: Option<Vec3>,
 }
 
-/// Raycast from cursor onto terrain to track the world position under the mouse.
+/// Raycast from cursor onto the heightmap to track the world position under the mouse.
 pub fn update_cursor_position(
-    mut ray_cast: MeshRayCast,
     camera_query: Query<(&Camera, &GlobalTransform), With<CityCamera>>,
     window: Query<&Window>,
     egui_input: Res<EguiWantsInput>,
-    terrain_query: Query<(), With<TerrainMesh>>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
     mut cursor_pos: ResMut<CursorWorldPosition>,
 ) {
     cursor_pos.position = None;
@@ -115,61 +141,33 @@ pub fn update_cursor_position(
         return;
     };
 
-    let filter = |entity: Entity| terrain_query.contains(entity);
-    let settings = MeshRayCastSettings::default().with_filter(&filter);
-
-    let hits = ray_cast.cast_ray(ray, &settings);
-    if let Some((_, hit)) = hits.first() {
-        cursor_pos.position = Some(hit.point);
-    }
-}
-
-/// A timed notification message.
-pub struct Notification {
-    pub message: String,
-    pub timer: f32,
-}
-
-/// Queue of notification messages displayed to the player.
-#[derive(Resource, Default)]
-pub struct Notifications {
-    pub messages: Vec<Notification>,
+    cursor_pos.position = heightmap.raycast(ray, config.map_size);
 }
 
-impl Notifications {
-    pub fn push(&mut self, message: impl Into<String>, duration: f32) {
-        self.messages.push(Notification {
-            message: message.into(),
-            timer: duration,
-        });
-    }
+/// Pick a seed from the system clock for the new-game dialog's Random button.
+fn random_seed() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() ^ d.as_secs() as u32)
+        .unwrap_or(0)
 }
 
-/// Tick down notification timers and remove expired ones.
-pub fn tick_notifications(time: Res<Time>, mut notifications: ResMut<Notifications>) {
-    let dt = time.delta_secs();
-    for notif in &mut notifications.messages {
-        notif.timer -= dt;
-    }
-    notifications.messages.retain(|n| n.timer > 0.0);
-}
-
-/// Draw the HUD, toolbar, info panel, and notifications.
+/// Draw the HUD, toolbar, new-game dialog, economy debug panel and notifications.
 #[allow(clippy::too_many_arguments)]
 pub fn draw_ui(
     mut contexts: EguiContexts,
     game_time: Res<GameTime>,
+    budget: Res<CityBudget>,
     mut active_tool: ResMut<ActiveTool>,
     mut placement: ResMut<RoadPlacementState>,
-    cursor_pos: Res<CursorWorldPosition>,
     heightmap: Res<Heightmap>,
     config: Res<TerrainConfig>,
-    road_network: Res<RoadNetwork>,
-    resource_map: Res<ResourceMap>,
-    notifications: Res<Notifications>,
+    mut notifications: ResMut<Notifications>,
     buildings_query: Query<(&Building, &Transform)>,
     economy_debug: Res<EconomyDebug>,
     economy_debug_visible: Res<DebugVisible>,
+    mut new_game_dialog: ResMut<NewGameDialog>,
+    mut new_game_request: ResMut<NewGameRequest>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
 
@@ -198,11 +196,15 @@ pub fn draw_ui(
         ui.horizontal(|ui| {
             ui.label(format!("Speed: {}", game_time.speed.label()));
             ui.separator();
+            ui.label(format!("Funds: ${:.0}", budget.funds));
+            ui.separator();
             ui.label(format!("Producers: {}", producer_count));
             ui.separator();
             ui.label(format!("Residential: {}", residential_count));
             ui.separator();
             ui.label(format!("Workers: {}/{}", workers_provided, workers_needed));
+            ui.separator();
+            ui.label(format!("Seed: {} ({})", config.seed, config.profile.name));
         });
     });
 
@@ -220,6 +222,10 @@ pub fn draw_ui(
                     (ActiveTool::Road, "Road (R)"),
                     (ActiveTool::Zone, "Zone"),
                     (ActiveTool::Building, "Building"),
+                    (ActiveTool::Terraform, "Terraform (T)"),
+                    (ActiveTool::Survey, "Survey (V)"),
+                    (ActiveTool::Upgrade, "Upgrade (U)"),
+                    (ActiveTool::Bulldoze, "Bulldoze (B)"),
                 ];
 
                 for &(tool, label) in tools {
@@ -231,62 +237,71 @@ pub fn draw_ui(
                         placement.points.clear();
                     }
                 }
+
+                ui.add_space(16.0);
+                if ui.button("New Game").clicked() {
+                    new_game_dialog.open = true;
+                    new_game_dialog.seed_input = config.seed.to_string();
+                    new_game_dialog.profile = config.profile.clone();
+                }
+                if ui.button("Export Map").clicked() {
+                    export_heightmap(&heightmap, &config, &mut notifications);
+                }
             });
         });
 
-    // --- Info panel (bottom-left) ---
-    egui::Window::new("Info")
-        .anchor(egui::Align2::LEFT_BOTTOM, [88.0, -4.0])
-        .resizable(false)
-        .collapsible(false)
-        .title_bar(false)
-        .show(ctx, |ui| {
-            if let Some(pos) = cursor_pos.position {
-                let elevation = heightmap.sample_world(pos.x, pos.z, config.map_size);
-                ui.label(format!("Position: ({:.0}, {:.0})", pos.x, pos.z));
-                ui.label(format!("Elevation: {:.1}", elevation));
+    // --- New game dialog ---
+    if new_game_dialog.open {
+        egui::Window::new("New Game")
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.text_edit_singleline(&mut new_game_dialog.seed_input);
+                    if ui.button("Random").clicked() {
+                        new_game_dialog.seed_input = random_seed().to_string();
+                    }
+                });
 
-                if let Some(cell) = resource_map.sample_world(pos.x, pos.z, config.map_size) {
-                    ui.label(format!(
-                        "{} ({:.0}%)",
-                        cell.resource.label(),
-                        cell.richness * 100.0
-                    ));
+                // Presets, plus the current profile if it was loaded from a file
+                let mut choices = GenerationProfile::presets();
+                if !choices.contains(&config.profile) {
+                    choices.insert(0, config.profile.clone());
                 }
-
-                if let Some(node_id) = road_network.nearest_node(pos, 5.0) {
-                    if let Some(node) = road_network.node(node_id) {
-                        ui.separator();
-                        ui.label(format!("Road node ({} connections)", node.segments.len()));
-                    }
+                egui::ComboBox::from_label("Map type")
+                    .selected_text(new_game_dialog.profile.name.clone())
+                    .show_ui(ui, |ui| {
+                        for choice in choices {
+                            let selected = new_game_dialog.profile == choice;
+                            if ui.selectable_label(selected, &choice.name).clicked() {
+                                new_game_dialog.profile = choice;
+                            }
+                        }
+                    });
+
+                let seed = new_game_dialog.seed_input.trim().parse::<u32>().ok();
+                if seed.is_none() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, "Seed must be a whole number");
                 }
 
-                // Nearby building info
-                let mut nearest_building: Option<(&Building, f32)> = None;
-                for (b, t) in &buildings_query {
-                    let dist = t.translation.distance(pos);
-                    if dist < 10.0
-                        && (nearest_building.is_none() || dist < nearest_building.unwrap().1)
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(seed.is_some(), egui::Button::new("Generate")).clicked()
+                        && let Some(seed) = seed
                     {
-                        nearest_building = Some((b, dist));
-                    }
-                }
-                if let Some((b, _)) = nearest_building {
-                    let def = &BUILDING_DEFS[b.def_index];
-                    ui.separator();
-                    ui.label(def.label);
-                    ui.label(format!("Category: {:?}", def.category));
-                    if def.workers_required > 0 {
-                        ui.label(format!("Workers needed: {}", def.workers_required));
+                        new_game_request.0 = Some(NewGame {
+                            seed,
+                            profile: new_game_dialog.profile.clone(),
+                        });
+                        new_game_dialog.open = false;
                     }
-                    if def.workers_provided > 0 {
-                        ui.label(format!("Workers provided: {}", def.workers_provided));
+                    if ui.button("Cancel").clicked() {
+                        new_game_dialog.open = false;
                     }
-                }
-            } else {
-                ui.label("--");
-            }
-        });
+                });
+            });
+    }
 
     // --- Economy debug panel (F3) ---
     if economy_debug_visible.0 {
@@ -319,6 +334,12 @@ pub fn draw_ui(
                 ui.label(format!("Producer: {}", producer_status));
                 ui.separator();
 
+                ui.label(format!(
+                    "Exhausted: {} ({} closed)",
+                    economy_debug.exhausted_producers, economy_debug.closed_producers,
+                ));
+                ui.separator();
+
                 let residential_status = if economy_debug.residential_viable {
                     format!("viable ({} candidates)", economy_debug.residential_candidates)
                 } else {
@@ -351,3 +372,172 @@ pub fn draw_ui(
 
     Ok(())
 }
+
+/// Draw the info panel: what is under the cursor (elevation, water, resource
+/// deposit, road node, nearest building).
+#[allow(clippy::too_many_arguments)]
+pub fn draw_info_panel(
+    mut contexts: EguiContexts,
+    cursor_pos: Res<CursorWorldPosition>,
+    heightmap: Res<Heightmap>,
+    config: Res<TerrainConfig>,
+    road_network: Res<RoadNetwork>,
+    road_upgrades: Res<RoadUpgrades>,
+    resource_map: Res<ResourceMap>,
+    deposits: Res<ResourceDeposits>,
+    water_bodies: Res<WaterBodies>,
+    buildings_query: Query<(&Building, &Transform, Has<Exhausted>, Has<Disconnected>)>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Info")
+        .anchor(egui::Align2::LEFT_BOTTOM, [88.0, -4.0])
+        .resizable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .show(ctx, |ui| {
+            if let Some(pos) = cursor_pos.position {
+                let elevation = heightmap.sample_world(pos.x, pos.z, config.map_size);
+                ui.label(format!("Position: ({:.0}, {:.0})", pos.x, pos.z));
+                ui.label(format!("Elevation: {:.1}", elevation));
+
+                if let Some(body) = water_bodies.body_at_world(pos.x, pos.z, config.map_size) {
+                    ui.label(format!(
+                        "{} #{} ({:.0} sq units, surface {:.1})",
+                        body.kind.label(),
+                        body.id.0,
+                        body.area,
+                        body.surface,
+                    ));
+                }
+
+                for entry in resource_map.known_world(pos.x, pos.z, config.map_size) {
+                    if let Some(deposit) =
+                        deposits.deposit_at_world(pos.x, pos.z, config.map_size, entry.resource)
+                    {
+                        ui.label(format!(
+                            "{}, {:.0}% remaining (here {:.0}%)",
+                            deposit.label(),
+                            deposit.remaining_fraction(&resource_map) * 100.0,
+                            entry.richness * 100.0,
+                        ));
+                    }
+                }
+                let (row, col) = resource_map.world_to_cell(pos.x, pos.z, config.map_size);
+                if !resource_map.is_surveyed(row, col) {
+                    ui.label("Not surveyed");
+                }
+
+                if let Some(node_id) = road_network.nearest_node(pos, 5.0) {
+                    if let Some(node) = road_network.node(node_id) {
+                        ui.separator();
+                        ui.label(format!("Road node ({} connections)", node.segments.len()));
+                    }
+                }
+
+                if let Some((segment_id, _)) =
+                    road_network.nearest_segment_point(Vec2::new(pos.x, pos.z), SEGMENT_PICK_RADIUS)
+                    && let Some(segment) = road_network.segment(segment_id)
+                {
+                    ui.separator();
+                    ui.label(format!(
+                        "{} road, {:.0} units",
+                        segment.road_type.label(),
+                        road_network.segment_length(segment_id).unwrap_or(0.0),
+                    ));
+                    if let Some(upgrade) = road_upgrades.pending.get(&segment_id) {
+                        ui.label(format!(
+                            "Upgrading to {}: {:.0}%",
+                            upgrade.target.label(),
+                            upgrade.progress() * 100.0,
+                        ));
+                    }
+                }
+
+                // Nearby building info
+                let mut nearest_building: Option<(&Building, &Transform, bool, bool, f32)> = None;
+                for (b, t, exhausted, disconnected) in &buildings_query {
+                    let dist = t.translation.distance(pos);
+                    if dist < 10.0
+                        && (nearest_building.is_none() || dist < nearest_building.unwrap().4)
+                    {
+                        nearest_building = Some((b, t, exhausted, disconnected, dist));
+                    }
+                }
+                if let Some((b, t, exhausted, disconnected, _)) = nearest_building {
+                    let def = &BUILDING_DEFS[b.def_index];
+                    ui.separator();
+                    ui.label(def.label);
+                    ui.label(format!("Category: {:?}", def.category));
+                    if def.workers_required > 0 {
+                        ui.label(format!("Workers needed: {}", def.workers_required));
+                    }
+                    if def.workers_provided > 0 {
+                        ui.label(format!("Workers provided: {}", def.workers_provided));
+                    }
+                    if let Some(resource) = def.extracts_from
+                        && let Some(deposit) = deposits.deposit_at_world(
+                            t.translation.x,
+                            t.translation.z,
+                            config.map_size,
+                            resource,
+                        )
+                    {
+                        ui.label(format!("Extracting: {}", deposit.label()));
+                    }
+                    if exhausted && let Some(resource) = def.extracts_from {
+                        ui.colored_label(
+                            egui::Color32::LIGHT_RED,
+                            format!("{} exhausted, closing soon", resource.label()),
+                        );
+                    }
+                    if disconnected {
+                        ui.colored_label(egui::Color32::LIGHT_RED, "No road access, abandoning soon");
+                    }
+                }
+            } else {
+                ui.label("--");
+            }
+        });
+
+    Ok(())
+}
+
+/// Per-resource filter for the resource overlay, shown with the F3 debug view.
+pub fn draw_resource_filter(
+    mut contexts: EguiContexts,
+    visible: Res<DebugVisible>,
+    mut overlay: ResMut<ResourceOverlay>,
+) -> Result {
+    if !visible.0 {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+
+    // Only flag a change when a box is toggled, since that repaints the overlay
+    let mut shown = overlay.shown;
+    egui::Window::new("Resources")
+        .anchor(egui::Align2::RIGHT_TOP, [-4.0, 40.0])
+        .resizable(false)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            for resource in ResourceType::ALL {
+                let [r, g, b, _] = resource.color();
+                let swatch = egui::Color32::from_rgb(
+                    (r * 255.0) as u8,
+                    (g * 255.0) as u8,
+                    (b * 255.0) as u8,
+                );
+                ui.horizontal(|ui| {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, swatch);
+                    ui.checkbox(&mut shown[resource as usize], resource.label());
+                });
+            }
+        });
+    if shown != overlay.shown {
+        overlay.shown = shown;
+    }
+
+    Ok(())
+}
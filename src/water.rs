@@ -0,0 +1,879 @@
+use bevy::prelude::*;
+#[cfg(feature = "gui")]
+use bevy::asset::RenderAssetUsages;
+#[cfg(feature = "gui")]
+use bevy::mesh::{Indices, PrimitiveTopology};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
+
+use crate::terrain::{Heightmap, TerrainConfig};
+#[cfg(feature = "gui")]
+use crate::terrain::WaterSurface;
+
+/// Fraction of the map's cells that must drain through a cell for it to
+/// become a river. 1% of a 256x256 grid is ~650 cells of catchment.
+const RIVER_CATCHMENT_FRACTION: f32 = 0.01;
+
+/// Narrowest and widest river channel, in world units.
+const MIN_RIVER_WIDTH: f32 = 2.0;
+const MAX_RIVER_WIDTH: f32 = 10.0;
+
+/// Water surface sits this fraction of the channel depth above the bed.
+const RIVER_FILL: f32 = 0.75;
+
+/// Minimum depth (filled height above ground) for a depression cell to hold a lake.
+const LAKE_MIN_DEPTH: f32 = 0.3;
+
+/// Lakes smaller than this many cells are treated as dry hollows.
+const LAKE_MIN_CELLS: usize = 16;
+
+/// Small rise added per cell when filling depressions, so every filled flat
+/// still drains in a definite direction.
+const FILL_EPSILON: f32 = 1e-4;
+
+// ---------------------------------------------------------------------------
+// Flow routing
+// ---------------------------------------------------------------------------
+
+/// Min-heap entry for priority-flood (lowest height pops first).
+struct FloodCell {
+    height: f32,
+    index: usize,
+}
+
+impl PartialEq for FloodCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.height == other.height
+    }
+}
+
+impl Eq for FloodCell {}
+
+impl PartialOrd for FloodCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloodCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so BinaryHeap behaves as a min-heap
+        other.height.total_cmp(&self.height)
+    }
+}
+
+/// Drainage of every heightmap cell toward the map edge.
+///
+/// - `filled`: heights with every closed depression filled to its spill point.
+/// - `receivers`: the cell each cell drains into (`None` on the map edge).
+/// - `accumulation`: number of cells draining through each cell, itself included.
+pub struct FlowField {
+    pub filled: Vec<f32>,
+    pub receivers: Vec<Option<usize>>,
+    pub accumulation: Vec<u32>,
+}
+
+/// Route flow across the heightmap using a priority-flood from the map edge.
+///
+/// Cells are visited lowest-first starting from the border, so each cell's
+/// receiver is the already-visited neighbor that reached it. Pits are raised
+/// to their spill height in `filled`, which guarantees every cell drains to
+/// the edge. Accumulation is summed in reverse visiting order (upstream first).
+pub fn compute_flow(heightmap: &Heightmap) -> FlowField {
+    let res = heightmap.resolution as usize;
+    let count = res * res;
+    let mut filled = heightmap.heights.clone();
+    let mut receivers = vec![None; count];
+    let mut visited = vec![false; count];
+    let mut order = Vec::with_capacity(count);
+    let mut open = BinaryHeap::new();
+
+    for row in 0..res {
+        for col in 0..res {
+            if row == 0 || col == 0 || row == res - 1 || col == res - 1 {
+                let index = row * res + col;
+                visited[index] = true;
+                open.push(FloodCell { height: filled[index], index });
+            }
+        }
+    }
+
+    while let Some(FloodCell { index, .. }) = open.pop() {
+        order.push(index);
+        let row = index / res;
+        let col = index % res;
+
+        for dr in -1i32..=1 {
+            for dc in -1i32..=1 {
+                if dr == 0 && dc == 0 {
+                    continue;
+                }
+                let r = row as i32 + dr;
+                let c = col as i32 + dc;
+                if r < 0 || c < 0 || r >= res as i32 || c >= res as i32 {
+                    continue;
+                }
+                let neighbor = r as usize * res + c as usize;
+                if visited[neighbor] {
+                    continue;
+                }
+                visited[neighbor] = true;
+                filled[neighbor] = filled[neighbor].max(filled[index] + FILL_EPSILON);
+                receivers[neighbor] = Some(index);
+                open.push(FloodCell { height: filled[neighbor], index: neighbor });
+            }
+        }
+    }
+
+    let mut accumulation = vec![1u32; count];
+    for &index in order.iter().rev() {
+        if let Some(receiver) = receivers[index] {
+            accumulation[receiver] += accumulation[index];
+        }
+    }
+
+    FlowField { filled, receivers, accumulation }
+}
+
+// ---------------------------------------------------------------------------
+// River network
+// ---------------------------------------------------------------------------
+
+/// A single river polyline, ordered downstream.
+///
+/// `points` are world-space positions of the water surface; `widths` holds the
+/// channel width at each point.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct River {
+    pub points: Vec<Vec3>,
+    pub widths: Vec<f32>,
+}
+
+/// All rivers on the map, plus a per-cell mask of cells covered by a channel.
+#[derive(Resource)]
+pub struct RiverNetwork {
+    pub rivers: Vec<River>,
+    cells: Vec<bool>,
+    resolution: u32,
+}
+
+impl RiverNetwork {
+    /// Whether a river channel covers this grid cell.
+    pub fn is_river(&self, row: u32, col: u32) -> bool {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.cells[(row * self.resolution + col) as usize]
+    }
+
+    /// Whether a river channel covers the world-space (x, z) position.
+    #[cfg(feature = "gui")]
+    pub fn is_river_world(&self, x: f32, z: f32, map_size: f32) -> bool {
+        let half = map_size / 2.0;
+        let cell_size = map_size / self.resolution as f32;
+        let col = ((x + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        let row = ((z + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        self.is_river(row, col)
+    }
+}
+
+/// Channel width for a cell with the given catchment, in world units.
+fn river_width(accumulation: u32, threshold: u32) -> f32 {
+    let t = (accumulation as f32 / threshold as f32).sqrt();
+    (MIN_RIVER_WIDTH * t).clamp(MIN_RIVER_WIDTH, MAX_RIVER_WIDTH)
+}
+
+/// Trace river paths as cell index lists, each ordered downstream.
+///
+/// A cell is a river once enough upstream area drains through it. Each path
+/// starts at a source (a river cell with no river upstream) and ends at the
+/// sea, the map edge, or the junction where it joins an already-traced river.
+fn trace_river_cells(
+    heightmap: &Heightmap,
+    flow: &FlowField,
+    threshold: u32,
+    water_level: f32,
+) -> Vec<Vec<usize>> {
+    let count = flow.accumulation.len();
+    let is_river = |i: usize| flow.accumulation[i] >= threshold && heightmap.heights[i] >= water_level;
+
+    let mut has_river_upstream = vec![false; count];
+    for i in 0..count {
+        if is_river(i)
+            && let Some(receiver) = flow.receivers[i]
+        {
+            has_river_upstream[receiver] = true;
+        }
+    }
+
+    // Trace from the highest sources first so the longest stems stay continuous
+    let mut sources: Vec<usize> = (0..count)
+        .filter(|&i| is_river(i) && !has_river_upstream[i])
+        .collect();
+    sources.sort_by(|&a, &b| flow.filled[b].total_cmp(&flow.filled[a]));
+
+    let mut visited = vec![false; count];
+    let mut paths = Vec::new();
+
+    for source in sources {
+        let mut path = Vec::new();
+        let mut current = Some(source);
+        while let Some(i) = current {
+            path.push(i);
+            if visited[i] || heightmap.heights[i] < water_level {
+                break;
+            }
+            visited[i] = true;
+            current = flow.receivers[i];
+        }
+        if path.len() >= 2 {
+            paths.push(path);
+        }
+    }
+
+    paths
+}
+
+/// Smooth a polyline's XZ with a few passes of neighbor averaging,
+/// keeping the endpoints fixed so junctions stay connected.
+fn smooth_path(points: &mut [Vec3], passes: usize) {
+    if points.len() < 3 {
+        return;
+    }
+    for _ in 0..passes {
+        let prev = points.to_vec();
+        for i in 1..points.len() - 1 {
+            let avg = (prev[i - 1] + prev[i] * 2.0 + prev[i + 1]) / 4.0;
+            points[i].x = avg.x;
+            points[i].z = avg.z;
+        }
+    }
+}
+
+/// Lower terrain along a river so the channel bed follows `beds`.
+/// Cells inside the half-width are cut to the bed; a bank of one cell
+/// on each side blends back up to the original ground. Returns covered cells.
+fn carve_channel(
+    heightmap: &mut Heightmap,
+    points: &[Vec3],
+    widths: &[f32],
+    beds: &[f32],
+    map_size: f32,
+    covered: &mut [bool],
+) {
+    let res = heightmap.resolution as i32;
+    let cell_size = map_size / res as f32;
+    let half = map_size / 2.0;
+
+    for i in 0..points.len() - 1 {
+        let (a, b) = (points[i], points[i + 1]);
+        let length = Vec2::new(b.x - a.x, b.z - a.z).length();
+        let steps = ((length / (cell_size * 0.5)).ceil() as usize).max(1);
+
+        for s in 0..=steps {
+            let t = s as f32 / steps as f32;
+            let center = a.lerp(b, t);
+            let half_width = (widths[i] + (widths[i + 1] - widths[i]) * t) / 2.0;
+            let bed = beds[i] + (beds[i + 1] - beds[i]) * t;
+            let bank = half_width + cell_size;
+
+            let reach = (bank / cell_size).ceil() as i32;
+            let center_col = ((center.x + half) / cell_size).round() as i32;
+            let center_row = ((center.z + half) / cell_size).round() as i32;
+
+            for row in (center_row - reach)..=(center_row + reach) {
+                for col in (center_col - reach)..=(center_col + reach) {
+                    if row < 0 || col < 0 || row >= res || col >= res {
+                        continue;
+                    }
+                    let x = col as f32 * cell_size - half;
+                    let z = row as f32 * cell_size - half;
+                    let dist = Vec2::new(x - center.x, z - center.z).length();
+                    if dist > bank {
+                        continue;
+                    }
+
+                    let index = (row * res + col) as usize;
+                    let ground = heightmap.heights[index];
+                    let target = if dist <= half_width {
+                        covered[index] = true;
+                        bed
+                    } else {
+                        let blend = (dist - half_width) / (bank - half_width);
+                        bed + (ground - bed) * blend
+                    };
+                    heightmap.heights[index] = ground.min(target);
+                }
+            }
+        }
+    }
+}
+
+/// Derive rivers from flow accumulation and carve them into the heightmap.
+///
+/// Runs after erosion and before biome classification so biomes, resources
+/// and the terrain mesh all see the carved channels. Each river's bed descends
+/// monotonically downstream, and rivers that reach the sea or map edge are
+/// cut down below `water_level` at their mouth.
+pub fn generate_rivers(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    mut heightmap: ResMut<Heightmap>,
+) {
+    let res = heightmap.resolution;
+    let cell_size = config.map_size / res as f32;
+    let half = config.map_size / 2.0;
+    let threshold = ((res * res) as f32 * RIVER_CATCHMENT_FRACTION) as u32;
+
+    let flow = compute_flow(&heightmap);
+    let paths = trace_river_cells(&heightmap, &flow, threshold, config.water_level);
+
+    let mut rivers = Vec::with_capacity(paths.len());
+    let mut covered = vec![false; (res * res) as usize];
+
+    for path in paths {
+        let mut points: Vec<Vec3> = path
+            .iter()
+            .map(|&i| {
+                let row = (i as u32 / res) as f32;
+                let col = (i as u32 % res) as f32;
+                Vec3::new(col * cell_size - half, heightmap.heights[i], row * cell_size - half)
+            })
+            .collect();
+        smooth_path(&mut points, 2);
+
+        let widths: Vec<f32> = path
+            .iter()
+            .map(|&i| river_width(flow.accumulation[i], threshold))
+            .collect();
+
+        // Bed never rises downstream and reaches the sea at the final point
+        let mut beds = Vec::with_capacity(points.len());
+        let mut bed = f32::MAX;
+        for (point, &width) in points.iter().zip(&widths) {
+            let depth = width * 0.25;
+            let ground = heightmap.sample_world(point.x, point.z, config.map_size);
+            bed = bed.min(ground - depth);
+            beds.push(bed);
+        }
+        // Rivers reaching the sea or map edge are cut down to sea level at the
+        // mouth; tributaries keep the bed of the river they join
+        let mouth = path[path.len() - 1];
+        let reaches_sea = flow.receivers[mouth].is_none() || heightmap.heights[mouth] < config.water_level;
+        if reaches_sea && let Some(last) = beds.last_mut() {
+            *last = last.min(config.water_level - 0.5);
+        }
+
+        carve_channel(&mut heightmap, &points, &widths, &beds, config.map_size, &mut covered);
+
+        for ((point, &width), &bed) in points.iter_mut().zip(&widths).zip(&beds) {
+            point.y = bed + width * 0.25 * RIVER_FILL;
+        }
+
+        rivers.push(River { points, widths });
+    }
+
+    commands.insert_resource(RiverNetwork {
+        rivers,
+        cells: covered,
+        resolution: res,
+    });
+}
+
+/// Build a ribbon mesh along every river and spawn it as a water surface.
+///
+/// Same cross-section approach as road meshes: each polyline point gets a
+/// left and right vertex at the river's width, stitched into triangles.
+#[cfg(feature = "gui")]
+pub fn spawn_river_meshes(
+    mut commands: Commands,
+    rivers: Res<RiverNetwork>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for river in &rivers.rivers {
+        let points = &river.points;
+        if points.len() < 2 {
+            continue;
+        }
+        let base_vertex = positions.len() as u32;
+
+        for (i, &center) in points.iter().enumerate() {
+            let forward = if i < points.len() - 1 {
+                (points[i + 1] - center).normalize_or_zero()
+            } else {
+                (center - points[i - 1]).normalize_or_zero()
+            };
+            let right = Vec3::new(-forward.z, 0.0, forward.x).normalize_or_zero();
+            // Slightly wider than the carved bed so the surface meets the banks
+            let half_width = river.widths[i] / 2.0 + 0.5;
+
+            let left_pt = center - right * half_width;
+            let right_pt = center + right * half_width;
+            positions.push([left_pt.x, center.y, left_pt.z]);
+            positions.push([right_pt.x, center.y, right_pt.z]);
+            normals.push([0.0, 1.0, 0.0]);
+            normals.push([0.0, 1.0, 0.0]);
+
+            let v = i as f32 / (points.len() - 1) as f32;
+            uvs.push([0.0, v]);
+            uvs.push([1.0, v]);
+        }
+
+        for i in 0..(points.len() as u32 - 1) {
+            let bl = base_vertex + i * 2;
+            let br = bl + 1;
+            let tl = bl + 2;
+            let tr = bl + 3;
+            indices.extend_from_slice(&[bl, br, tl, tl, br, tr]);
+        }
+    }
+
+    if positions.is_empty() {
+        return;
+    }
+
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices));
+
+    commands.spawn((
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.1, 0.3, 0.5, 0.7),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.3,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        })),
+        WaterSurface,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Water bodies
+// ---------------------------------------------------------------------------
+
+/// Unique identifier for a water body. Also tags the body's mesh entity.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct WaterBodyId(pub u32);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaterBodyKind {
+    /// Below sea level and connected to the map edge.
+    Ocean,
+    /// Enclosed depression, filled to its spill height.
+    Lake,
+}
+
+impl WaterBodyKind {
+    #[cfg(feature = "gui")]
+    pub fn label(&self) -> &str {
+        match self {
+            WaterBodyKind::Ocean => "Ocean",
+            WaterBodyKind::Lake => "Lake",
+        }
+    }
+}
+
+/// A connected region of standing water.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct WaterBody {
+    pub id: WaterBodyId,
+    pub kind: WaterBodyKind,
+    /// Y elevation of the water surface.
+    pub surface: f32,
+    /// Grid cell indices (row * resolution + col) covered by water.
+    pub cells: Vec<usize>,
+    /// Land cells directly bordering the water (4-neighborhood).
+    pub shoreline: Vec<usize>,
+    /// Surface area in square world units.
+    pub area: f32,
+}
+
+/// All standing water on the map, with a per-cell lookup of which body
+/// (if any) covers each grid cell.
+#[derive(Resource)]
+pub struct WaterBodies {
+    pub bodies: Vec<WaterBody>,
+    cell_body: Vec<Option<WaterBodyId>>,
+    shore: Vec<bool>,
+    resolution: u32,
+}
+
+impl WaterBodies {
+    pub fn body(&self, id: WaterBodyId) -> Option<&WaterBody> {
+        self.bodies.get(id.0 as usize)
+    }
+
+    /// The water body covering this grid cell, if any.
+    pub fn body_at(&self, row: u32, col: u32) -> Option<&WaterBody> {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.cell_body[(row * self.resolution + col) as usize].and_then(|id| self.body(id))
+    }
+
+    pub fn is_water(&self, row: u32, col: u32) -> bool {
+        self.body_at(row, col).is_some()
+    }
+
+    /// Whether this dry cell borders any water body.
+    pub fn is_shoreline(&self, row: u32, col: u32) -> bool {
+        let row = row.min(self.resolution - 1);
+        let col = col.min(self.resolution - 1);
+        self.shore[(row * self.resolution + col) as usize]
+    }
+
+    /// The water body covering the world-space (x, z) position, if any.
+    #[cfg(feature = "gui")]
+    pub fn body_at_world(&self, x: f32, z: f32, map_size: f32) -> Option<&WaterBody> {
+        let half = map_size / 2.0;
+        let cell_size = map_size / self.resolution as f32;
+        let col = ((x + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        let row = ((z + half) / cell_size).clamp(0.0, (self.resolution - 1) as f32) as u32;
+        self.body_at(row, col)
+    }
+
+    /// Whether standing water covers the world-space (x, z) position.
+    #[cfg(feature = "gui")]
+    pub fn is_water_world(&self, x: f32, z: f32, map_size: f32) -> bool {
+        self.body_at_world(x, z, map_size).is_some()
+    }
+}
+
+/// Flood-fill 4-connected cells matching `include`, starting from `start`.
+pub fn flood_component(
+    start: usize,
+    res: usize,
+    include: impl Fn(usize) -> bool,
+    assigned: &mut [bool],
+) -> Vec<usize> {
+    let mut cells = Vec::new();
+    let mut stack = vec![start];
+    assigned[start] = true;
+
+    while let Some(i) = stack.pop() {
+        cells.push(i);
+        let row = i / res;
+        let col = i % res;
+        let neighbors = [
+            (row > 0).then(|| i - res),
+            (row + 1 < res).then(|| i + res),
+            (col > 0).then(|| i - 1),
+            (col + 1 < res).then(|| i + 1),
+        ];
+        for n in neighbors.into_iter().flatten() {
+            if !assigned[n] && include(n) {
+                assigned[n] = true;
+                stack.push(n);
+            }
+        }
+    }
+
+    cells
+}
+
+/// Classify standing water into distinct bodies.
+///
+/// Ocean: cells below `water_level` flood-filled from the map edge; each
+/// connected region is its own body at sea level. Lake: cells that the
+/// depression fill raises by at least `LAKE_MIN_DEPTH`, grouped by
+/// connectivity, each with the spill height as its surface. Inland basins
+/// below sea level become lakes at their own spill height rather than
+/// joining the sea. Runs after rivers are carved.
+pub fn generate_water_bodies(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    heightmap: Res<Heightmap>,
+) {
+    let res = heightmap.resolution as usize;
+    let count = res * res;
+    let heights = &heightmap.heights;
+    let cell_area = (config.map_size / res as f32).powi(2);
+    let flow = compute_flow(&heightmap);
+
+    let mut assigned = vec![false; count];
+    let mut components: Vec<(WaterBodyKind, Vec<usize>)> = Vec::new();
+
+    // Oceans: seed from every edge cell below sea level
+    let below_sea = |i: usize| heights[i] < config.water_level;
+    for i in 0..count {
+        let (row, col) = (i / res, i % res);
+        let on_edge = row == 0 || col == 0 || row == res - 1 || col == res - 1;
+        if on_edge && !assigned[i] && below_sea(i) {
+            let cells = flood_component(i, res, below_sea, &mut assigned);
+            components.push((WaterBodyKind::Ocean, cells));
+        }
+    }
+
+    // Lakes: remaining depressions deep enough to hold water
+    let in_lake = |i: usize| flow.filled[i] - heights[i] >= LAKE_MIN_DEPTH;
+    for i in 0..count {
+        if !assigned[i] && in_lake(i) {
+            let cells = flood_component(i, res, in_lake, &mut assigned);
+            if cells.len() >= LAKE_MIN_CELLS {
+                components.push((WaterBodyKind::Lake, cells));
+            }
+        }
+    }
+
+    let mut bodies = Vec::with_capacity(components.len());
+    let mut cell_body = vec![None; count];
+
+    for (kind, cells) in components {
+        let id = WaterBodyId(bodies.len() as u32);
+        for &i in &cells {
+            cell_body[i] = Some(id);
+        }
+        let surface = match kind {
+            WaterBodyKind::Ocean => config.water_level,
+            WaterBodyKind::Lake => cells.iter().map(|&i| flow.filled[i]).fold(f32::MIN, f32::max),
+        };
+        let area = cells.len() as f32 * cell_area;
+        bodies.push(WaterBody {
+            id,
+            kind,
+            surface,
+            cells,
+            shoreline: Vec::new(),
+            area,
+        });
+    }
+
+    // Shoreline: dry cells touching a body
+    let mut shore = vec![false; count];
+    for i in 0..count {
+        if cell_body[i].is_some() {
+            continue;
+        }
+        let (row, col) = (i / res, i % res);
+        let neighbors = [
+            (row > 0).then(|| i - res),
+            (row + 1 < res).then(|| i + res),
+            (col > 0).then(|| i - 1),
+            (col + 1 < res).then(|| i + 1),
+        ];
+        for id in neighbors.into_iter().flatten().filter_map(|n| cell_body[n]) {
+            let shoreline = &mut bodies[id.0 as usize].shoreline;
+            if shoreline.last() != Some(&i) {
+                shoreline.push(i);
+            }
+            shore[i] = true;
+        }
+    }
+
+    commands.insert_resource(WaterBodies {
+        bodies,
+        cell_body,
+        shore,
+        resolution: res as u32,
+    });
+}
+
+/// Spawn one flat water mesh per water body at its surface height.
+///
+/// Every grid square with a corner in the body gets a quad, so the surface
+/// reaches slightly past the last water cell and tucks under the rising
+/// terrain instead of leaving a gap at the shore.
+#[cfg(feature = "gui")]
+pub fn spawn_water_bodies(
+    mut commands: Commands,
+    config: Res<TerrainConfig>,
+    water_bodies: Res<WaterBodies>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let res = water_bodies.resolution as usize;
+    let cell_size = config.map_size / res as f32;
+    let half = config.map_size / 2.0;
+
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgba(0.1, 0.3, 0.5, 0.7),
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 0.3,
+        ..default()
+    });
+
+    for body in &water_bodies.bodies {
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut vertex_of: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut squares: Vec<(usize, usize)> = Vec::new();
+
+        // Grid squares (top-left corner) touching any cell in the body
+        for &i in &body.cells {
+            let (row, col) = (i / res, i % res);
+            for r in row.saturating_sub(1)..=row.min(res - 2) {
+                for c in col.saturating_sub(1)..=col.min(res - 2) {
+                    squares.push((r, c));
+                }
+            }
+        }
+        squares.sort_unstable();
+        squares.dedup();
+
+        let mut vertex = |row: usize, col: usize, positions: &mut Vec<[f32; 3]>, uvs: &mut Vec<[f32; 2]>| {
+            *vertex_of.entry((row, col)).or_insert_with(|| {
+                positions.push([col as f32 * cell_size - half, body.surface, row as f32 * cell_size - half]);
+                uvs.push([col as f32 / (res - 1) as f32, row as f32 / (res - 1) as f32]);
+                positions.len() as u32 - 1
+            })
+        };
+
+        for (row, col) in squares {
+            let top_left = vertex(row, col, &mut positions, &mut uvs);
+            let top_right = vertex(row, col + 1, &mut positions, &mut uvs);
+            let bottom_left = vertex(row + 1, col, &mut positions, &mut uvs);
+            let bottom_right = vertex(row + 1, col + 1, &mut positions, &mut uvs);
+            indices.extend_from_slice(&[
+                top_left, bottom_left, top_right,
+                top_right, bottom_left, bottom_right,
+            ]);
+        }
+
+        if positions.is_empty() {
+            continue;
+        }
+
+        let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+        let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+            .with_inserted_indices(Indices::U32(indices));
+
+        commands.spawn((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material.clone()),
+            WaterSurface,
+            body.id,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// Square heightmap from a height function of (row, col).
+    fn heightmap_from(res: u32, height: impl Fn(u32, u32) -> f32) -> Heightmap {
+        let heights = (0..res * res).map(|i| height(i / res, i % res)).collect();
+        Heightmap { heights, resolution: res }
+    }
+
+    #[test]
+    fn pit_drains_to_spill_point() {
+        // West-facing slope with a pit dug at the center
+        let res = 9;
+        let pit = (4 * res + 4) as usize;
+        let heightmap = heightmap_from(res, |row, col| if (row, col) == (4, 4) { 0.5 } else { col as f32 });
+        let flow = compute_flow(&heightmap);
+
+        // Filled to its lowest neighbor (col 3), which it drains into
+        assert!(flow.filled[pit] > 3.0 && flow.filled[pit] < 3.01);
+        let receiver = flow.receivers[pit].unwrap();
+        assert_eq!(receiver % res as usize, 3);
+
+        // ...and on down to the west edge
+        let mut cell = pit;
+        while let Some(next) = flow.receivers[cell] {
+            assert!(flow.filled[next] < flow.filled[cell]);
+            cell = next;
+        }
+        assert_eq!(cell % res as usize, 0);
+    }
+
+    #[test]
+    fn flow_accumulates_downhill() {
+        // V-shaped valley along row 7, falling gently to the west edge
+        let res = 15;
+        let heightmap = heightmap_from(res, |row, col| (row as f32 - 7.0).abs() + col as f32 * 0.1);
+        let flow = compute_flow(&heightmap);
+        let valley = |col: u32| (7 * res + col) as usize;
+
+        for col in 2..res - 1 {
+            assert_eq!(flow.receivers[valley(col)], Some(valley(col - 1)));
+            assert!(flow.accumulation[valley(col - 1)] > flow.accumulation[valley(col)]);
+        }
+        // Every interior valley cell drains through the mouth
+        assert!(flow.accumulation[valley(1)] as usize >= (res - 2) as usize);
+        // Edge cells are sinks, and together they take all the flow
+        let edge_total: u32 = (0..(res * res) as usize)
+            .filter(|&i| flow.receivers[i].is_none())
+            .map(|i| flow.accumulation[i])
+            .sum();
+        assert_eq!(edge_total, res * res);
+    }
+
+    #[test]
+    fn carve_lowers_channel_to_bed() {
+        // 17x17 cells, 2 units each, flat at 10
+        let mut heightmap = heightmap_from(17, |_, _| 10.0);
+        let mut covered = vec![false; 17 * 17];
+        let points = [Vec3::new(-10.0, 10.0, 0.0), Vec3::new(10.0, 10.0, 0.0)];
+        carve_channel(&mut heightmap, &points, &[4.0, 4.0], &[6.0, 6.0], 34.0, &mut covered);
+
+        let center = (8 * 17 + 8) as usize;
+        assert_eq!(heightmap.heights[center], 6.0);
+        assert!(covered[center]);
+        // Banks blend back up, far ground is untouched, nothing is raised
+        assert!(heightmap.heights[center - 2 * 17] > 6.0);
+        assert_eq!(heightmap.heights[0], 10.0);
+        assert!(!covered[0]);
+        assert!(heightmap.heights.iter().all(|&h| h <= 10.0));
+    }
+
+    #[test]
+    fn bowl_becomes_lake_and_low_edge_ocean() {
+        // Plateau at 5 with a below-sea strip on the west edge, an 8x8 bowl
+        // 1 unit deep, and a 2x2 hollow too small for a lake
+        let res = 24;
+        let bowl = |row: u32, col: u32| (8..16).contains(&row) && (10..18).contains(&col);
+        let hollow = |row: u32, col: u32| (2..4).contains(&row) && (20..22).contains(&col);
+        let heightmap = heightmap_from(res, |row, col| {
+            if col < 3 {
+                0.0
+            } else if bowl(row, col) || hollow(row, col) {
+                4.0
+            } else {
+                5.0
+            }
+        });
+        let mut world = World::new();
+        world.insert_resource(TerrainConfig {
+            map_size: 48.0,
+            water_level: 1.0,
+            ..default()
+        });
+        world.insert_resource(heightmap);
+        world.run_system_once(generate_water_bodies).unwrap();
+        let water = world.resource::<WaterBodies>();
+
+        assert_eq!(water.bodies.len(), 2);
+        let ocean = water.body_at(12, 0).unwrap();
+        assert_eq!(ocean.kind, WaterBodyKind::Ocean);
+        assert_eq!(ocean.surface, 1.0);
+        assert_eq!(ocean.cells.len(), 3 * res as usize);
+
+        let lake = water.body_at(12, 12).unwrap();
+        assert_eq!(lake.kind, WaterBodyKind::Lake);
+        assert_eq!(lake.cells.len(), 64);
+        assert!((lake.surface - 5.0).abs() < 0.01);
+
+        assert!(!water.is_water(2, 20));
+        assert!(!water.is_water(12, 5));
+        assert!(water.is_shoreline(12, 3));
+        assert!(water.is_shoreline(7, 12));
+    }
+}